@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use crate::{core::apps::{icons::IconResolver, model::AppList, utils::get_icon_path}, toml_files::EntryConfig};
+
+pub fn merge_entries(apps: &mut Vec<AppList>, entries: &[EntryConfig], icons: &IconResolver) {
+    for entry in entries {
+        if !entry.alias.is_empty() {
+            match apps.iter_mut().find(|app| app.id == entry.alias) {
+                Some(app) => app.aliases.push(entry.name.clone()),
+                None => println!("Alias {:?} points to {:?}, but this app is not installed", entry.name, entry.alias),
+            }
+            continue;
+            // Alias is one more name of an installed app, not a new row
+        }
+        if entry.exec.trim().is_empty() {
+            println!("Entry {:?} has no exec and no alias, skipping it", entry.name);
+            continue;
+        }
+        apps.push(custom_app(entry, icons));
+    }
+}
+
+fn custom_app(entry: &EntryConfig, icons: &IconResolver) -> AppList {
+    let icon_path = if entry.icon.is_empty() { None } else { get_icon_path(&entry.icon, icons) };
+    AppList {
+        id: format!("entry:{}", entry.name),
+        // Not a desktop ID, but stable, so pins and history keep working
+        name: entry.name.clone(),
+        untranslated_name: entry.name.clone(),
+        generic_name: String::new(),
+        description: entry.description.clone(),
+        keywords: entry.keywords.clone(),
+        categories: Vec::new(),
+        aliases: Vec::new(),
+        exec: entry.exec.clone(),
+        icon: entry.icon.clone(),
+        icon_path: icon_path.unwrap_or_default(),
+        desktop_path: PathBuf::new(),
+        type_file: "Application".into(),
+        terminal: entry.terminal,
+        try_exec: None,
+        working_dir: None,
+        actions: Vec::new()
+    }
+}
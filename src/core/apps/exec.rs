@@ -0,0 +1,212 @@
+use crate::core::apps::model::AppList;
+
+pub fn split_exec(exec: &str) -> Option<Vec<String>> {
+    let unescaped = unescape_string(exec);
+    // Desktop files escape \s \n \t \r \\ on every string value, undo that first
+
+    let mut args: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quoted = false;
+    let mut chars = unescaped.chars();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' => quoted = false,
+                '\\' => match chars.next() {
+                    Some(next @ ('"' | '`' | '$' | '\\')) => current.push(next),
+                    // Only these chars can be escaped inside quotes
+                    Some(next) => {
+                        current.push('\\');
+                        current.push(next);
+                    }
+                    None => return None,
+                },
+                _ => current.push(c),
+            }
+        } else {
+            match c {
+                ' ' | '\t' | '\n' => {
+                    if in_arg {
+                        args.push(std::mem::take(&mut current));
+                        in_arg = false;
+                    }
+                    // Unquoted whitespace ends the argument
+                }
+                '"' => {
+                    quoted = true;
+                    in_arg = true;
+                }
+                _ => {
+                    current.push(c);
+                    in_arg = true;
+                }
+            }
+        }
+    }
+    if quoted {
+        return None;
+        // Exec line with unterminated quote is invalid
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Some(args)
+}
+
+pub fn expand_exec(exec: &str, icon: &str, app: &AppList) -> Option<Vec<String>> {
+    let mut argv: Vec<String> = Vec::new();
+
+    for arg in split_exec(exec)? {
+        if arg == "%i" {
+            if !icon.is_empty() {
+                argv.push("--icon".into());
+                argv.push(icon.to_string());
+            }
+            continue;
+            // %i expands to two arguments or to nothing
+        }
+
+        let mut expanded = String::new();
+        let mut chars = arg.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                expanded.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('%') => expanded.push('%'),
+                Some('c') => expanded.push_str(&app.name),
+                Some('k') => expanded.push_str(&app.desktop_path.to_string_lossy()),
+                _ => {}
+                // We never pass files or urls, so %f %F %u %U and deprecated codes expand to nothing
+            }
+        }
+
+        if expanded.is_empty() && arg.len() == 2 && arg.starts_with('%') && arg != "%%" {
+            continue;
+        }
+        // Argument that was only a field code is removed if it expanded to nothing
+        argv.push(expanded);
+    }
+
+    if argv.is_empty() {
+        return None;
+    }
+    Some(argv)
+}
+
+fn unescape_string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('\\') => result.push('\\'),
+            Some(next) => {
+                result.push('\\');
+                result.push(next);
+            }
+            // Keep other escapes for the quoting rules
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn app() -> AppList {
+        AppList {
+            name: "Text Editor".into(),
+            desktop_path: PathBuf::from("/usr/share/applications/editor.desktop"),
+            ..Default::default()
+        }
+    }
+
+    pub fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn splits_on_unquoted_whitespace() {
+        assert_eq!(split_exec("editor  --new-window\t-x"), Some(args(&["editor", "--new-window", "-x"])));
+        assert_eq!(split_exec("  "), Some(Vec::new()));
+    }
+
+    #[test]
+    fn quotes_keep_whitespace() {
+        assert_eq!(split_exec(r#"sh -c "echo hello world""#), Some(args(&["sh", "-c", "echo hello world"])));
+        assert_eq!(split_exec(r#"editor "" end"#), Some(args(&["editor", "", "end"])));
+        assert_eq!(split_exec(r#"editor --name="My Notes""#), Some(args(&["editor", "--name=My Notes"])));
+        assert_eq!(split_exec(r#"editor "my\sfile""#), Some(args(&["editor", "my file"])));
+    }
+
+    #[test]
+    fn escapes_inside_quotes() {
+        assert_eq!(split_exec(r#"sh -c "echo \\$HOME \\"hi\\"""#), Some(args(&["sh", "-c", r#"echo $HOME "hi""#])));
+        assert_eq!(split_exec(r#"sh -c "echo \\`date\\`""#), Some(args(&["sh", "-c", "echo `date`"])));
+        assert_eq!(split_exec(r#"sh -c "a\\b""#), Some(args(&["sh", "-c", r"a\b"])));
+        // Other escapes are kept as they are
+    }
+
+    #[test]
+    fn backslash_is_unescaped_twice() {
+        assert_eq!(split_exec(r#"printf "\\\\""#), Some(args(&["printf", r"\"])));
+        // \\\\ in desktop file is \\ after string unescape and \ after quoting rules
+        assert_eq!(split_exec(r"printf \\n"), Some(args(&["printf", r"\n"])));
+    }
+
+    #[test]
+    fn unterminated_quote_is_invalid() {
+        assert_eq!(split_exec(r#"editor "file"#), None);
+        assert_eq!(split_exec(r#"editor "file\\"#), None);
+        assert_eq!(expand_exec(r#"editor "file"#, "", &app()), None);
+    }
+
+    #[test]
+    fn percent_is_escaped_with_percent() {
+        assert_eq!(expand_exec("printf 100%%", "", &app()), Some(args(&["printf", "100%"])));
+        assert_eq!(expand_exec("printf %%", "", &app()), Some(args(&["printf", "%"])));
+    }
+
+    #[test]
+    fn icon_expands_to_two_args_or_none() {
+        assert_eq!(expand_exec("editor %i", "accessories-text-editor", &app()), Some(args(&["editor", "--icon", "accessories-text-editor"])));
+        assert_eq!(expand_exec("editor %i --new", "", &app()), Some(args(&["editor", "--new"])));
+    }
+
+    #[test]
+    fn name_and_desktop_path() {
+        assert_eq!(
+            expand_exec("editor --class=%c %k", "", &app()),
+            Some(args(&["editor", "--class=Text Editor", "/usr/share/applications/editor.desktop"]))
+        );
+    }
+
+    #[test]
+    fn file_codes_expand_to_nothing() {
+        assert_eq!(expand_exec("editor %f %F %u %U %d %D %n %N %v %m", "", &app()), Some(args(&["editor"])));
+        assert_eq!(expand_exec("editor --file=%f", "", &app()), Some(args(&["editor", "--file="])));
+        // Only an argument that was the field code alone is removed
+    }
+
+    #[test]
+    fn empty_command_is_invalid() {
+        assert_eq!(expand_exec("", "", &app()), None);
+        assert_eq!(expand_exec("%U", "", &app()), None);
+        assert_eq!(expand_exec("%i", "", &app()), None);
+    }
+}
@@ -0,0 +1,102 @@
+use std::{collections::HashMap, path::PathBuf, time::{SystemTime, UNIX_EPOCH}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::apps::storage::{load_versioned, save_versioned};
+
+const HISTORY_MAGIC: &[u8; 4] = b"STRH";
+const HISTORY_VERSION: u32 = 1;
+// Bump version every time History layout changes
+
+const DAY: u64 = 24 * 60 * 60;
+const RECENCY_WEIGHTS: [(u64, i32); 4] = [(4 * DAY, 100), (14 * DAY, 70), (31 * DAY, 50), (90 * DAY, 30)];
+const OLD_WEIGHT: i32 = 10;
+// Launch counts less the older it is, like Firefox frecency
+
+const FRECENCY_DIVIDER: i32 = 10;
+const MAX_FRECENCY_BOOST: i32 = 60;
+const QUERY_BOOST: i32 = 40;
+const MAX_QUERY_BOOST: i32 = 200;
+// Boost for the same query is big, so a learned app beats a better text match
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct History {
+    launches: Vec<Launch>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Launch {
+    id: String,
+    time: u64,
+    query: String
+}
+
+impl History {
+    pub fn load() -> History {
+        history_path()
+            .and_then(|path| load_versioned(&path, HISTORY_MAGIC, HISTORY_VERSION).ok())
+            .unwrap_or_default()
+        // Missing or old history starts empty
+    }
+
+    pub fn record(&mut self, id: &str, query: &str, max_entries: usize) {
+        self.launches.push(Launch { id: id.to_string(), time: now(), query: query.trim().to_lowercase() });
+        if self.launches.len() > max_entries {
+            let extra = self.launches.len() - max_entries;
+            self.launches.drain(..extra);
+        }
+        // Oldest launches are forgotten first
+        if let Err(e) = self.save() {
+            println!("Failed to save history: {}", e);
+        }
+    }
+
+    pub fn recent(&self) -> Vec<&str> {
+        let mut recent: Vec<&str> = Vec::new();
+        for launch in self.launches.iter().rev() {
+            if !recent.contains(&launch.id.as_str()) {
+                recent.push(&launch.id);
+            }
+        }
+        recent
+        // Last launched app first, every app once
+    }
+
+    pub fn boosts(&self, query: &str) -> HashMap<&str, i32> {
+        let query = query.trim().to_lowercase();
+        let now = now();
+        let mut frecency: HashMap<&str, i32> = HashMap::new();
+        let mut learned: HashMap<&str, i32> = HashMap::new();
+        for launch in &self.launches {
+            *frecency.entry(&launch.id).or_default() += recency_weight(now.saturating_sub(launch.time));
+            if !query.is_empty() && launch.query == query {
+                *learned.entry(&launch.id).or_default() += QUERY_BOOST;
+            }
+            // App that was opened with this query before
+        }
+
+        for (id, score) in frecency.iter_mut() {
+            *score = (*score / FRECENCY_DIVIDER).min(MAX_FRECENCY_BOOST);
+            *score += learned.get(id).copied().unwrap_or(0).min(MAX_QUERY_BOOST);
+        }
+        // Boost is added to the search score of every app
+        frecency
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = history_path().ok_or("no data dir")?;
+        save_versioned(&path, HISTORY_MAGIC, HISTORY_VERSION, self)
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join("stryde/history.bin"))
+}
+
+fn recency_weight(age: u64) -> i32 {
+    RECENCY_WEIGHTS.iter().find(|(max_age, _)| age <= *max_age).map(|(_, weight)| *weight).unwrap_or(OLD_WEIGHT)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0)
+}
@@ -0,0 +1,78 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use iced::widget::image::Handle;
+
+use crate::core::apps::{storage::save_atomic, utils::{file_stamp, resize_icon}};
+
+const ICON_MAGIC: &[u8; 4] = b"SRG2";
+// Every cached icon starts with magic, modified time and size of the icon file, width and height, then RGBA pixels
+const HEADER_LEN: usize = 28;
+
+pub fn cached_icon(path: &Path, size: u32) -> Option<Handle> {
+    let cache_path = icon_cache_path(path, size);
+    let stamp = file_stamp(path)?;
+
+    if let Some(cache_path) = &cache_path
+        && let Some(handle) = read_icon(cache_path, stamp) {
+        return Some(handle);
+    }
+    // Warm start, no decoding needed
+
+    let resized = resize_icon(path.to_str()?, size)?;
+    let (width, height) = resized.dimensions();
+    let pixels = resized.into_raw();
+
+    if let Some(cache_path) = &cache_path {
+        let _ = write_icon(cache_path, stamp, width, height, &pixels);
+    }
+    // If cache can't be written, icon still works
+    // Changed icon file overwrites its old cached copy, so cache never grows past one file per icon and size
+    Some(Handle::from_rgba(width, height, pixels))
+}
+
+fn icon_cache_path(path: &Path, size: u32) -> Option<PathBuf> {
+    let mut key = fnv1a(0xcbf29ce484222325, path.as_os_str().as_encoded_bytes());
+    key = fnv1a(key, &size.to_le_bytes());
+    // Other icon_size gives another key, changed icon file is checked with the header
+    Some(dirs::cache_dir()?.join("stryde/icons").join(format!("{:016x}.rgba", key)))
+}
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+    // Stable between Rust versions, unlike DefaultHasher
+}
+
+fn read_icon(cache_path: &Path, (mtime, len): (u64, u64)) -> Option<Handle> {
+    let data = fs::read(cache_path).ok()?;
+    let (header, pixels) = data.split_at_checked(HEADER_LEN)?;
+    if &header[..4] != ICON_MAGIC {
+        return None;
+    }
+    if u64::from_le_bytes(header[4..12].try_into().ok()?) != mtime || u64::from_le_bytes(header[12..20].try_into().ok()?) != len {
+        return None;
+    }
+    // Icon file changed since it was cached
+    let width = u32::from_le_bytes(header[20..24].try_into().ok()?);
+    let height = u32::from_le_bytes(header[24..28].try_into().ok()?);
+    if pixels.len() != width as usize * height as usize * 4 {
+        return None;
+    }
+    // Broken or half written file is ignored and written again
+    Some(Handle::from_rgba(width, height, pixels.to_vec()))
+}
+
+fn write_icon(cache_path: &Path, (mtime, len): (u64, u64), width: u32, height: u32, pixels: &[u8]) -> std::io::Result<()> {
+    let mut data = Vec::with_capacity(HEADER_LEN + pixels.len());
+    data.extend_from_slice(ICON_MAGIC);
+    data.extend_from_slice(&mtime.to_le_bytes());
+    data.extend_from_slice(&len.to_le_bytes());
+    data.extend_from_slice(&width.to_le_bytes());
+    data.extend_from_slice(&height.to_le_bytes());
+    data.extend_from_slice(pixels);
+
+    save_atomic(cache_path, &data)
+}
@@ -0,0 +1,278 @@
+use std::{collections::HashMap, fs, path::{Path, PathBuf}, sync::OnceLock};
+
+use crate::core::apps::{scanner::data_dirs, utils::current_desktops};
+
+const EXTENSIONS: [&str; 2] = ["png", "svg"];
+// Spec also has xpm, but we can't draw it
+
+pub struct IconResolver {
+    themes: Vec<IconTheme>,
+    pixmap_dirs: Vec<PathBuf>,
+    size: u16,
+    scale: u16,
+}
+
+struct IconTheme {
+    subdirs: Vec<ThemeSubdir>,
+}
+
+struct ThemeSubdir {
+    paths: Vec<PathBuf>,
+    size: u16,
+    scale: u16,
+    min_size: u16,
+    max_size: u16,
+    threshold: u16,
+    kind: SubdirKind,
+    files: OnceLock<HashMap<String, PathBuf>>,
+    // Files are read once when dir is used for the first time
+}
+
+enum SubdirKind {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+impl IconResolver {
+    pub fn new(theme_name: &str, size: u16, scale: u16) -> Self {
+        let base_dirs = icon_base_dirs();
+        let mut names: Vec<String> = Vec::new();
+        theme_chain(theme_name, &base_dirs, &mut names);
+        if !names.iter().any(|name| name == "hicolor") {
+            names.push("hicolor".into());
+        }
+        // Theme, all themes it inherits from, and hicolor as the last fallback
+
+        let themes = names.iter().filter_map(|name| load_theme(name, &base_dirs)).collect();
+
+        let mut pixmap_dirs: Vec<PathBuf> = data_dirs().iter().map(|dir| dir.join("pixmaps")).collect();
+        pixmap_dirs.push(PathBuf::from("/usr/share/pixmaps"));
+
+        Self { themes, pixmap_dirs, size, scale }
+    }
+
+    pub fn find(&self, icon_name: &str) -> Option<PathBuf> {
+        if icon_name.is_empty() {
+            return None;
+        }
+        let path = Path::new(icon_name);
+        if path.is_absolute() {
+            return path.is_file().then(|| path.to_path_buf());
+        }
+        // Desktop files can point directly to an icon file
+
+        let name = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("png" | "svg" | "xpm") => path.file_stem()?.to_str()?,
+            _ => icon_name,
+        };
+        // Some desktop files have Icon=name.png, spec says it must be without extension
+
+        for theme in &self.themes {
+            if let Some(path) = theme.find(name, self.size, self.scale) {
+                return Some(path);
+            }
+        }
+        // Look in theme, then in its parents
+
+        for dir in &self.pixmap_dirs {
+            for ext in EXTENSIONS {
+                let path = dir.join(format!("{}.{}", name, ext));
+                if path.is_file() {
+                    return Some(path);
+                }
+            }
+        }
+        // Old apps put icons in /usr/share/pixmaps
+        None
+    }
+}
+
+impl IconTheme {
+    fn find(&self, name: &str, size: u16, scale: u16) -> Option<PathBuf> {
+        for subdir in self.subdirs.iter().filter(|subdir| subdir.matches_size(size, scale)) {
+            if let Some(path) = subdir.find(name) {
+                return Some(path);
+            }
+        }
+        // Exact size first
+
+        let mut closest: Option<(u32, PathBuf)> = None;
+        for subdir in &self.subdirs {
+            let distance = subdir.size_distance(size, scale);
+            if closest.as_ref().is_some_and(|(best, _)| *best <= distance) {
+                continue;
+            }
+            if let Some(path) = subdir.find(name) {
+                closest = Some((distance, path));
+            }
+        }
+        // Then size that is the closest
+        closest.map(|(_, path)| path)
+    }
+}
+
+impl ThemeSubdir {
+    fn find(&self, name: &str) -> Option<PathBuf> {
+        let files = self.files.get_or_init(|| {
+            let mut files: HashMap<String, PathBuf> = HashMap::new();
+            for dir in &self.paths {
+                for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+                    if let Ok(file_name) = entry.file_name().into_string() {
+                        files.entry(file_name).or_insert_with(|| entry.path());
+                    }
+                }
+            }
+            files
+        });
+        // One read_dir per dir is much faster than checking every icon name with stat
+
+        EXTENSIONS.iter().find_map(|ext| files.get(&format!("{}.{}", name, ext)).cloned())
+    }
+
+    fn matches_size(&self, size: u16, scale: u16) -> bool {
+        if self.scale != scale {
+            return false;
+        }
+        match self.kind {
+            SubdirKind::Fixed => self.size == size,
+            SubdirKind::Scalable => self.min_size <= size && size <= self.max_size,
+            SubdirKind::Threshold => self.size.saturating_sub(self.threshold) <= size && size <= self.size + self.threshold,
+        }
+    }
+
+    fn size_distance(&self, size: u16, scale: u16) -> u32 {
+        let wanted = size as u32 * scale as u32;
+        let dir_scale = self.scale as u32;
+        let (min, max) = match self.kind {
+            SubdirKind::Fixed => (self.size as u32 * dir_scale, self.size as u32 * dir_scale),
+            SubdirKind::Scalable => (self.min_size as u32 * dir_scale, self.max_size as u32 * dir_scale),
+            SubdirKind::Threshold => (
+                self.size.saturating_sub(self.threshold) as u32 * dir_scale,
+                (self.size + self.threshold) as u32 * dir_scale,
+            ),
+        };
+        // Distance in real pixels, like in the icon theme spec
+        if wanted < min {
+            min - wanted
+        } else {
+            wanted.saturating_sub(max)
+        }
+    }
+}
+
+pub fn icon_scale(scale_factor: f32) -> u16 {
+    (scale_factor.ceil() as u16).max(1)
+    // Themes only have integer scales like @2x, fractional scale uses the next bigger one
+}
+
+pub fn user_icon_theme(configured: &str) -> String {
+    if !configured.is_empty() {
+        return configured.to_string();
+    }
+    // icon_theme from config.toml wins
+
+    let kde_first = current_desktops().iter().any(|desktop| desktop == "KDE");
+    let (kde, gtk) = (kde_icon_theme(), gtk_icon_theme());
+    let theme = if kde_first { kde.or(gtk) } else { gtk.or(kde) };
+    // Use the same theme as the rest of desktop
+    theme.unwrap_or("hicolor".into())
+}
+
+fn gtk_icon_theme() -> Option<String> {
+    let config_dir = dirs::config_dir()?;
+    ["gtk-4.0/settings.ini", "gtk-3.0/settings.ini"].iter().find_map(|file| {
+        let content = fs::read_to_string(config_dir.join(file)).ok()?;
+        ini_value(&content, "Settings", "gtk-icon-theme-name")
+    })
+}
+
+fn kde_icon_theme() -> Option<String> {
+    let content = fs::read_to_string(dirs::config_dir()?.join("kdeglobals")).ok()?;
+    ini_value(&content, "Icons", "Theme")
+}
+
+fn ini_value(content: &str, section: &str, key: &str) -> Option<String> {
+    let value = parse_ini(content).get(section)?.get(key)?.trim_matches('"').to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+fn parse_ini(content: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut current = String::new();
+    for line in content.lines().map(str::trim) {
+        if let Some(section) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            current = section.to_string();
+        } else if let Some((key, value)) = line.split_once('=')
+            && !line.starts_with('#') {
+            sections.entry(current.clone()).or_default().insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    sections
+}
+
+fn icon_base_dirs() -> Vec<PathBuf> {
+    let mut base_dirs: Vec<PathBuf> = Vec::new();
+    if let Some(home) = dirs::home_dir() {
+        base_dirs.push(home.join(".icons"));
+    }
+    base_dirs.extend(data_dirs().iter().map(|dir| dir.join("icons")));
+    // Same order as in the icon theme spec
+    base_dirs
+}
+
+fn theme_chain(name: &str, base_dirs: &[PathBuf], names: &mut Vec<String>) {
+    if names.iter().any(|known| known == name) {
+        return;
+    }
+    // Themes can inherit each other in a loop
+    names.push(name.to_string());
+
+    let Some(index) = base_dirs.iter().find_map(|dir| fs::read_to_string(dir.join(name).join("index.theme")).ok()) else {
+        return;
+    };
+    if let Some(parents) = parse_ini(&index).get("Icon Theme").and_then(|section| section.get("Inherits")) {
+        for parent in parents.split(',').map(str::trim).filter(|parent| !parent.is_empty()) {
+            theme_chain(parent, base_dirs, names);
+        }
+    }
+}
+
+fn load_theme(name: &str, base_dirs: &[PathBuf]) -> Option<IconTheme> {
+    let roots: Vec<PathBuf> = base_dirs.iter().map(|dir| dir.join(name)).filter(|dir| dir.is_dir()).collect();
+    let index = roots.iter().find_map(|root| fs::read_to_string(root.join("index.theme")).ok())?;
+    // Theme can be split between many base dirs, but index.theme is read from the first one
+
+    let index = parse_ini(&index);
+    let theme_section = index.get("Icon Theme")?;
+
+    let mut subdir_names: Vec<&str> = Vec::new();
+    for key in ["Directories", "ScaledDirectories"] {
+        if let Some(value) = theme_section.get(key) {
+            subdir_names.extend(value.split(',').map(str::trim).filter(|dir| !dir.is_empty()));
+        }
+    }
+
+    let subdirs = subdir_names.iter().filter_map(|&subdir| {
+        let section = index.get(subdir)?;
+        let number = |key: &str| section.get(key).and_then(|value| value.parse::<u16>().ok());
+        let size = number("Size")?;
+        let kind = match section.get("Type").map(String::as_str) {
+            Some("Fixed") => SubdirKind::Fixed,
+            Some("Scalable") => SubdirKind::Scalable,
+            _ => SubdirKind::Threshold,
+        };
+        Some(ThemeSubdir {
+            paths: roots.iter().map(|root| root.join(subdir)).collect(),
+            size,
+            scale: number("Scale").unwrap_or(1),
+            min_size: number("MinSize").unwrap_or(size),
+            max_size: number("MaxSize").unwrap_or(size),
+            threshold: number("Threshold").unwrap_or(2),
+            kind,
+            files: OnceLock::new(),
+        })
+    }).collect();
+
+    Some(IconTheme { subdirs })
+}
@@ -1,54 +1,78 @@
-use std::{fs, path::{Path, PathBuf}};
+use std::{collections::HashMap, path::{Path, PathBuf}};
 
-use crate::core::apps::{model::{AppList, CacheFile}, parser::parse_data, utils::last_modified};
+use crate::{core::apps::{entries::merge_entries, icons::{IconResolver, user_icon_theme}, locale::current_locale, model::{AppList, CacheFile}, parser::parse_data, scanner::{application_dirs, scan_desktop_files}, state::State, storage::{load_versioned, save_versioned}, utils::{current_desktops, find_in_path, glob_match}}, toml_files::Config};
 
-pub fn indexing() -> Option<Vec<AppList>> {
-    let cache_dir = dirs::cache_dir()?.join("stryde");
-    // Get path to stryde cache
-    let cache_path = cache_dir.join("cache.bin");
+const CACHE_MAGIC: &[u8; 4] = b"STRY";
+const CACHE_VERSION: u32 = 5;
+// Bump version every time CacheFile or AppList layout changes
 
-    if fs::metadata(&cache_dir).is_err() {
-        fs::create_dir_all(&cache_dir).ok()?;
-    }
-    // If dir not exist, I make it
-
-    let app_dir = dirs::home_dir()?.join(".local/share/applications");
-    let system_dir = PathBuf::from("/usr/share/applications");
-    let flatpak_dir = Path::new("/var/lib/flatpak/app");
-
-    let hash = {
-        let m1 = last_modified(&app_dir).unwrap_or(0);
-        let m2 = last_modified(&system_dir).unwrap_or(0);
-        let m3 = last_modified(&flatpak_dir).unwrap_or(0);
-        m1 + m2 + m3
-    };
-    // Get sum of dates when was modified the apps dir (system, flatpak, user)
-
-    if let Ok(cache) = load_cache(&cache_path) {
-        if cache.hash == hash {
-            return Some(cache.apps);
-        }
+pub fn indexing(config: &Config) -> Option<Vec<AppList>> {
+    let cache_path = dirs::cache_dir().map(|dir| dir.join("stryde/cache.bin"));
+    // Get path to stryde cache, apps are still indexed without it
+
+    let app_dirs = application_dirs(&config.apps.search_paths);
+    // Get every applications dir from XDG data dirs and config
+
+    let locale = current_locale();
+    // Names in cache are translated, so cache is valid only for the same locale
+    let desktops = current_desktops();
+    let desktop = desktops.join(":");
+    // OnlyShowIn and NotShowIn are checked for this desktop, so cache is valid only for it
+    let icon_theme = user_icon_theme(&config.icon_theme);
+    let icon_size = config.layout.icon_size;
+    let icons = IconResolver::new(&icon_theme, icon_size, 1);
+    // Icon paths in cache come from this icon theme and size, scale is checked later by the UI
+
+    let cached = cache_path.as_deref()
+        .and_then(|path| load_cache(path).ok())
+        .filter(|cache| cache.locale == locale && cache.desktop == desktop && cache.icon_theme == icon_theme && cache.icon_size == icon_size)
+        .map(|cache| cache.files)
+        .unwrap_or_default();
+    let old_stamps: Vec<(PathBuf, u64, u64)> = cached.iter().map(|file| (file.path.clone(), file.mtime, file.size)).collect();
+    // Remember what was in cache to know if it must be saved again
+
+    let cached = cached.into_iter().map(|file| (file.path.clone(), file)).collect::<HashMap<_, _>>();
+    let files = parse_data(scan_desktop_files(&app_dirs), cached, &locale, &desktops, &icons);
+    // Parse only desktop files that were added or changed
+
+    let new_stamps: Vec<(PathBuf, u64, u64)> = files.iter().map(|file| (file.path.clone(), file.mtime, file.size)).collect();
+    let cache_file = CacheFile { locale, desktop, icon_theme, icon_size, files };
+    if new_stamps != old_stamps
+        && let Some(cache_path) = &cache_path
+        && let Err(e) = save_cache(&cache_file, cache_path) {
+        println!("Failed to save app cache: {}", e);
     }
-    // If cache loaded without errors and the cache hash equals to new one return the cache
+    // Save cache only if some file was added, changed or removed
+    // Read only or full cache dir only makes next start slower, apps are still shown
 
-    let cache_file = CacheFile {
-        hash,
-        apps: parse_data() // Parse apps
-    };
-    save_cache(&cache_file, &cache_path).ok()?;
-    return Some(cache_file.apps);
+    let mut apps = installed_apps(cache_file.files.into_iter().filter_map(|file| file.app).collect());
+    merge_entries(&mut apps, &config.entries, &icons);
+    // Entries from config are added every time, so cache never has old ones
+    Some(visible_apps(apps, &config.apps.hidden_apps))
+}
 
+fn installed_apps(apps: Vec<AppList>) -> Vec<AppList> {
+    apps.into_iter().filter(|app| {
+        app.try_exec.as_ref().is_none_or(|try_exec| find_in_path(try_exec).is_some())
+    }).collect()
+    // Hide apps whose TryExec binary is not installed, cache keeps them so they show up once it is
+}
+
+fn visible_apps(apps: Vec<AppList>, hidden_apps: &[String]) -> Vec<AppList> {
+    let hidden: Vec<String> = hidden_apps.iter().cloned().chain(State::load().hidden).collect();
+    apps.into_iter().filter(|app| {
+        let short_id = app.id.strip_suffix(".desktop").unwrap_or(&app.id);
+        !hidden.iter().any(|pattern| glob_match(pattern, &app.id) || glob_match(pattern, short_id))
+    }).collect()
+    // Hidden from config and hidden with keybind, filtered after cache so unhiding works right away
 }
 
 fn load_cache(path: &Path) -> Result<CacheFile, Box<dyn std::error::Error>> {
-     // read cache file and return the apps and hash
-     let data = fs::read(path)?;
-     Ok(bincode::deserialize(&data)?)
+    load_versioned(path, CACHE_MAGIC, CACHE_VERSION)
+    // read cache file and return the parsed desktop files
 }
 
 fn save_cache(cache: &CacheFile, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-       let data = bincode::serialize(cache)?;
-       fs::write(path, data)?;
-       Ok(())
-       // Save the cache
-}
\ No newline at end of file
+    save_versioned(path, CACHE_MAGIC, CACHE_VERSION, cache)
+    // Save the cache with format header
+}
@@ -0,0 +1,177 @@
+use std::{collections::HashMap, io, os::unix::process::{CommandExt, ExitStatusExt}, path::Path, process::{Command, ExitStatus, Stdio}, thread, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use iced::futures::{channel::oneshot::{self, Receiver}, future::{self, Either}};
+
+const STRIPPED_ENV: [&str; 4] = ["DESKTOP_STARTUP_ID", "XDG_ACTIVATION_TOKEN", "WAYLAND_SOCKET", "DESKTOP_AUTOSTART_ID"];
+// Startup tokens belong to Stryde launch and WAYLAND_SOCKET is an fd only Stryde can use
+// WAYLAND_DISPLAY and DISPLAY stay, apps need them to open a window
+
+const FALLBACK_MAX_FD: i32 = 65536;
+const EARLY_EXIT_TIMEOUT: Duration = Duration::from_secs(1);
+// App that fails right after start usually exits in this time
+
+pub fn spawn_detached(argv: &[String], working_dir: Option<&Path>, env: Option<&HashMap<String, String>>) -> io::Result<Receiver<ExitStatus>> {
+    let (program, args) = argv.split_first().ok_or(io::ErrorKind::InvalidInput)?;
+    let mut command = Command::new(program);
+    command.args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // App never writes to terminal Stryde was started from
+    for var in STRIPPED_ENV {
+        command.env_remove(var);
+    }
+    if let Some(env) = env {
+        command.envs(env);
+    }
+    // Variables for this app from config
+    if let Some(working_dir) = working_dir {
+        command.current_dir(working_dir);
+    }
+    unsafe { command.pre_exec(detach) };
+
+    let mut child = command.spawn()?;
+    let (sender, receiver) = oneshot::channel();
+    thread::spawn(move || {
+        if let Ok(status) = child.wait() {
+            let _ = sender.send(status);
+        }
+    });
+    // Wait for the app in background, so it never stays a zombie while Stryde is open
+    // If Stryde exits first, init takes the app
+    Ok(receiver)
+}
+
+pub async fn early_exit_error(exit: Receiver<ExitStatus>, name: String) -> Option<String> {
+    let (timer, timeout) = oneshot::channel::<()>();
+    thread::spawn(move || {
+        thread::sleep(EARLY_EXIT_TIMEOUT);
+        let _ = timer.send(());
+    });
+    // Only this thread sleeps, executor workers stay free for icons and indexing
+
+    match future::select(exit, timeout).await {
+        Either::Left((Ok(status), _)) if !status.success() => Some(match (status.code(), status.signal()) {
+            (Some(code), _) => format!("{} exited with code {}", name, code),
+            (None, Some(signal)) => format!("{} was killed by signal {}", name, signal),
+            _ => format!("{} exited with an error", name),
+        }),
+        _ => None,
+        // Still running after timeout means it started fine
+    }
+}
+
+pub fn scope_command(argv: &[String], systemd_run: Option<&Path>, app_id: &str, app_name: &str) -> Vec<String> {
+    let Some(systemd_run) = systemd_run else {
+        return argv.to_vec();
+    };
+    // Not a systemd system, start the app directly
+
+    let mut command: Vec<String> = vec![
+        systemd_run.to_string_lossy().into_owned(),
+        "--user".into(),
+        "--scope".into(),
+        "--quiet".into(),
+        "--collect".into(),
+        format!("--unit={}", scope_name(app_id)),
+        format!("--description={}", app_name),
+        "--".into(),
+    ];
+    command.extend(argv.iter().cloned());
+    command
+    // systemd-run makes the scope, then runs the app itself inside it
+}
+
+fn scope_name(app_id: &str) -> String {
+    let id = app_id.strip_suffix(".desktop").unwrap_or(app_id);
+    let random = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.subsec_nanos()).unwrap_or(0) ^ std::process::id();
+    format!("app-stryde-{}-{:08x}.scope", escape_unit(id), random)
+    // app-<launcher>-<ApplicationID>-<RANDOM>.scope from XDG cgroup naming
+}
+
+fn escape_unit(id: &str) -> String {
+    let mut escaped = String::new();
+    for (index, byte) in id.bytes().enumerate() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b':' | b'_' => escaped.push(byte as char),
+            b'.' if index > 0 => escaped.push('.'),
+            _ => escaped.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    escaped
+    // Same as systemd-escape, "-" must be escaped because it separates the parts of the name
+}
+
+fn detach() -> io::Result<()> {
+    if unsafe { libc::setsid() } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // Own session, so closing the terminal or Stryde doesn't send SIGHUP to the app
+
+    let marked = unsafe { libc::syscall(libc::SYS_close_range, 3u32, u32::MAX, libc::CLOSE_RANGE_CLOEXEC) };
+    if marked < 0 {
+        let max_fd = unsafe { libc::sysconf(libc::_SC_OPEN_MAX) };
+        let max_fd = if max_fd > 0 { (max_fd as i32).min(FALLBACK_MAX_FD) } else { FALLBACK_MAX_FD };
+        for fd in 3..max_fd {
+            unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) };
+        }
+        // Old kernels don't have close_range
+    }
+    // Inherited fds are closed on exec, not now, Rust still needs its own pipe to report exec errors
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use iced::futures::executor::block_on;
+
+    use super::*;
+    use crate::core::apps::exec::tests::args;
+
+    #[test]
+    fn scope_command_without_systemd_run() {
+        let argv = args(&["editor", "--new"]);
+        assert_eq!(scope_command(&argv, None, "org.example.App.desktop", "App"), argv);
+        // Not a systemd system, app is started directly
+    }
+
+    #[test]
+    fn scope_command_with_systemd_run() {
+        let argv = args(&["editor", "--new"]);
+        let command = scope_command(&argv, Some(Path::new("/usr/bin/systemd-run")), "org.example.App.desktop", "App");
+        assert_eq!(command[..5], args(&["/usr/bin/systemd-run", "--user", "--scope", "--quiet", "--collect"]));
+        assert!(command[5].starts_with("--unit=app-stryde-org.example.App-"));
+        assert_eq!(command[6], "--description=App");
+        assert_eq!(command[7..], args(&["--", "editor", "--new"]));
+    }
+
+    #[test]
+    fn early_exit_is_reported() {
+        let exit = spawn_detached(&args(&["sh", "-c", "exit 3"]), None, None).unwrap();
+        assert_eq!(block_on(early_exit_error(exit, "App".into())), Some("App exited with code 3".into()));
+        let exit = spawn_detached(&args(&["true"]), None, None).unwrap();
+        assert_eq!(block_on(early_exit_error(exit, "App".into())), None);
+        let exit = spawn_detached(&args(&["sleep", "5"]), None, None).unwrap();
+        assert_eq!(block_on(early_exit_error(exit, "App".into())), None);
+        // Still running after timeout
+    }
+
+    #[test]
+    fn escape_unit_like_systemd_escape() {
+        assert_eq!(escape_unit("org.gnome.Text_Editor"), "org.gnome.Text_Editor");
+        assert_eq!(escape_unit("gnome-terminal"), "gnome\\x2dterminal");
+        assert_eq!(escape_unit(".hidden.app"), "\\x2ehidden.app");
+        assert_eq!(escape_unit("My App"), "My\\x20App");
+        assert_eq!(escape_unit("entry:Dev Server"), "entry:Dev\\x20Server");
+        assert_eq!(escape_unit("café"), "caf\\xc3\\xa9");
+    }
+
+    #[test]
+    fn scope_name_shape() {
+        let name = scope_name("org.example.my-app.desktop");
+        let random = name.strip_prefix("app-stryde-org.example.my\\x2dapp-").and_then(|rest| rest.strip_suffix(".scope")).unwrap();
+        assert_eq!(random.len(), 8);
+        assert!(random.chars().all(|c| c.is_ascii_hexdigit()));
+        assert!(scope_name("entry:Dev Server").starts_with("app-stryde-entry:Dev\\x20Server-"));
+    }
+}
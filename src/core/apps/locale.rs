@@ -0,0 +1,71 @@
+use std::env;
+
+use freedesktop_file_parser::{LocaleString, LocaleStringList};
+
+pub fn current_locale() -> String {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    // Same priority as gettext, first non-empty var wins
+
+    let (locale, modifier) = match locale.split_once('@') {
+        Some((locale, modifier)) => (locale, Some(modifier)),
+        None => (locale.as_str(), None),
+    };
+    let locale = locale.split('.').next().unwrap_or_default();
+    // Drop the encoding, de_DE.UTF-8@euro -> de_DE@euro
+
+    if locale.is_empty() || locale == "C" || locale == "POSIX" {
+        return String::new();
+    }
+    match modifier {
+        Some(modifier) => format!("{}@{}", locale, modifier),
+        None => locale.to_string(),
+    }
+}
+
+pub fn locale_variants(locale: &str) -> Vec<String> {
+    if locale.is_empty() {
+        return Vec::new();
+    }
+    let (locale_without_modifier, modifier) = match locale.split_once('@') {
+        Some((locale, modifier)) => (locale, Some(modifier)),
+        None => (locale, None),
+    };
+    let (lang, country) = match locale_without_modifier.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (locale_without_modifier, None),
+    };
+
+    let mut variants: Vec<String> = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        variants.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        variants.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        variants.push(format!("{}@{}", lang, modifier));
+    }
+    variants.push(lang.to_string());
+    // Order from the desktop entry spec: lang_COUNTRY@MODIFIER, lang_COUNTRY, lang@MODIFIER, lang
+    variants
+}
+
+pub fn localized(value: &LocaleString, variants: &[String]) -> String {
+    variants
+        .iter()
+        .find_map(|variant| value.variants.get(variant))
+        .unwrap_or(&value.default)
+        .clone()
+}
+
+pub fn localized_list(value: &LocaleStringList, variants: &[String]) -> Vec<String> {
+    variants
+        .iter()
+        .find_map(|variant| value.variants.get(variant))
+        .unwrap_or(&value.default)
+        .clone()
+}
@@ -2,4 +2,16 @@ pub mod parser;
 pub mod scanner;
 pub mod model;
 pub mod indexer;
-pub mod utils;
\ No newline at end of file
+pub mod utils;
+pub mod exec;
+pub mod locale;
+pub mod watcher;
+pub mod icon_cache;
+pub mod icons;
+pub mod search;
+pub mod history;
+pub mod state;
+pub mod entries;
+pub mod terminal;
+pub mod launch;
+pub mod storage;
@@ -1,23 +1,58 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct AppList {
+    pub id: String,
     pub name: String,
+    pub untranslated_name: String,
+    pub generic_name: String,
     pub description: String,
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+    #[serde(skip)]
+    pub aliases: Vec<String>,
+    // Filled from config after cache is loaded, never cached
     pub exec: String,
+    pub icon: String,
     pub icon_path: std::path::PathBuf,
+    pub desktop_path: std::path::PathBuf,
     pub type_file: String,
-    pub terminal: bool
+    pub terminal: bool,
+    pub try_exec: Option<String>,
+    pub working_dir: Option<String>,
+    pub actions: Vec<AppAction>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AppAction {
+    pub name: String,
+    pub exec: String,
+    pub icon: String
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Handler {
     pub image_handler: Option<iced::widget::image::Handle>,
     pub svg_handler: Option<iced::widget::svg::Handle>,
+    pub stamp: Option<(u64, u64)>,
+    // Icon file when it was loaded, to know when it must be loaded again
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CacheFile {
-    pub hash: u64,
-    pub apps: Vec<AppList>
-}
\ No newline at end of file
+    pub locale: String,
+    pub desktop: String,
+    pub icon_theme: String,
+    pub icon_size: u16,
+    pub files: Vec<CachedDesktopFile>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CachedDesktopFile {
+    pub id: String,
+    pub path: std::path::PathBuf,
+    pub mtime: u64,
+    pub size: u64,
+    pub app: Option<AppList>
+    // None if file is hidden, broken or not for this desktop, it still shadows files with same id
+}
@@ -1,75 +1,138 @@
-use std::{ffi::OsStr, fs, path::PathBuf};
+use std::{collections::{HashMap, HashSet}, fs, path::{Path, PathBuf}};
 
 use freedesktop_file_parser::EntryType;
+use rayon::prelude::*;
 
-use crate::core::apps::{model::AppList, scanner::scan_desktop_files, utils::{get_icon_path}};
+use crate::core::apps::{icons::IconResolver, locale::{locale_variants, localized, localized_list}, model::{AppAction, AppList, CachedDesktopFile}, utils::{file_stamp, get_icon_path}};
 
-pub fn parse_data() -> Vec<AppList> {
-    let desktops_paths: Vec<PathBuf> = scan_desktop_files();
-    let mut apps_info: Vec<AppList> = Vec::new();
-    for entry in &desktops_paths {
-        if entry.extension() != Some(OsStr::new("desktop")) || entry.is_dir()
-        {
+pub fn parse_data(desktops_paths: Vec<(String, PathBuf)>, mut cached: HashMap<PathBuf, CachedDesktopFile>, locale: &str, desktops: &[String], icons: &IconResolver) -> Vec<CachedDesktopFile> {
+    let variants = locale_variants(locale);
+    // Locale keys to try, like Name[de_DE] then Name[de]
+    let mut files: Vec<CachedDesktopFile> = Vec::new();
+    let mut to_parse: Vec<usize> = Vec::new();
+    let mut seen_ids: HashSet<String> = HashSet::new();
+    for (id, path) in desktops_paths {
+        if !seen_ids.insert(id.clone()) {
             continue;
         }
-        // Move to the next if current is not a .desktop file
-        
-        let content = match fs::read_to_string(entry) {
-            Ok(content) => content,
-            Err(_) => continue,
-        };
-        // Get content from .desktop file
-        let desktop_file = match freedesktop_file_parser::parse(&content) {
-            Ok(file) => file,
-            Err(_) => continue,
-        };
-        // Get all attr from desktop file
+        // Desktop file with the same id in a higher priority dir shadows this one, even if it is hidden or broken
 
-        if desktop_file.entry.hidden == Some(true) || desktop_file.entry.no_display == Some(true) {
+        let Some((mtime, size)) = file_stamp(&path) else {
+            continue;
+        };
+        if let Some(file) = cached.remove(&path)
+            && file.id == id && file.mtime == mtime && file.size == size {
+            files.push(file);
             continue;
         }
-        // If app in .desktop file is hidden, skip app
-
-        let name = desktop_file.entry.name.default;
-        // Get name of the app in .desktop file
-
-        let description = desktop_file.entry.comment;
-        // Get description of the app in .desktop file
-
-        let icon_name = desktop_file.entry.icon.unwrap_or_default().content.trim().to_string();
-        // Get icon of the app in .desktop file
-
-        let icon_path = get_icon_path(&icon_name);
-
-
-        if let EntryType::Application(app) = &desktop_file.entry.entry_type {
-            let mut exec = match &app.exec {
-                Some(exec) => exec.clone(),
-                None => continue,
-            };
-            let terminal = match &app.terminal {
-                Some(terminal) => terminal.clone(),
-                None => false
-            };
-            // Get exec command of the app in .desktop file
-
-            for arg in ["%u", "%f", "%U", "%F", "%i", "%c", "%k"] {
-                exec = exec.replace(arg, "");
-            }
-            // Remove freedesktop exec placeholders
-
-            apps_info.push(
-                AppList {
-                    name: name,
-                    description: description.unwrap_or_default().default,
-                    exec: exec,
-                    icon_path: icon_path.unwrap_or_default(),
-                    type_file: desktop_file.entry.entry_type.to_string(),
-                    terminal: terminal
-                }
-            );
-            // Push app in list of apps
+        // File didn't change since last time, reuse it from cache
+
+        to_parse.push(files.len());
+        files.push(CachedDesktopFile { id, path, mtime, size, app: None });
+        // Parse only new and changed files
+    }
+
+    let parsed: Vec<Option<AppList>> = to_parse.par_iter().map(|&index| {
+        parse_file(&files[index].id, &files[index].path, &variants, desktops, icons)
+    }).collect();
+    for (index, app) in to_parse.into_iter().zip(parsed) {
+        files[index].app = app;
+    }
+    // Read and parse files on all cores, order of files stays the same
+    files
+}
+
+fn parse_file(id: &str, entry: &Path, variants: &[String], desktops: &[String], icons: &IconResolver) -> Option<AppList> {
+    let content = fs::read_to_string(entry).ok()?;
+    // Get content from .desktop file
+    let desktop_file = freedesktop_file_parser::parse(&content).ok()?;
+    // Get all attr from desktop file
+
+    if desktop_file.entry.hidden == Some(true) || desktop_file.entry.no_display == Some(true) {
+        return None;
+    }
+    // If app in .desktop file is hidden, skip app
+
+    if !show_in_desktop(&desktop_file.entry.only_show_in, &desktop_file.entry.not_show_in, desktops) {
+        return None;
+    }
+    // Skip apps that are only for other desktops (like GNOME settings on KDE)
+
+    let EntryType::Application(app) = &desktop_file.entry.entry_type else {
+        return None;
+    };
+    // Only applications can be launched
+
+    let exec = app.exec.clone()?;
+    // Get exec command of the app in .desktop file, field codes are expanded on launch
+
+    let name = localized(&desktop_file.entry.name, variants);
+    let untranslated_name = desktop_file.entry.name.default.clone();
+    // Get name of the app in .desktop file, translated one and default one
+
+    let generic_name = desktop_file.entry.generic_name.as_ref().map(|generic_name| localized(generic_name, variants)).unwrap_or_default();
+    // Get generic name like "Web Browser", it is used only for search
+
+    let description = desktop_file.entry.comment.as_ref().map(|comment| localized(comment, variants)).unwrap_or_default();
+    // Get description of the app in .desktop file
+
+    let icon_name = desktop_file.entry.icon.as_ref().map(|icon| icon.content.trim().to_string()).unwrap_or_default();
+    // Get icon of the app in .desktop file
+
+    let icon_path = get_icon_path(&icon_name, icons);
+
+    let terminal = app.terminal.unwrap_or(false);
+
+    let try_exec = app.try_exec.as_ref().map(|try_exec| try_exec.trim().to_string()).filter(|try_exec| !try_exec.is_empty());
+    // Binary that must exist to show the app, checked every time apps are loaded
+
+    let working_dir = app.path.as_ref().map(|path| path.trim().to_string()).filter(|path| !path.is_empty());
+    // Path= key, app runs from this dir
+
+    let keywords = app.keywords.as_ref().map(|keywords| localized_list(keywords, variants)).unwrap_or_default();
+    let categories = app.categories.clone().unwrap_or_default();
+
+    let actions = app.actions.iter().flatten().filter_map(|id| {
+        let action = desktop_file.actions.get(id)?;
+        Some(AppAction {
+            name: localized(&action.name, variants),
+            exec: action.exec.clone()?,
+            icon: action.icon.as_ref().map(|icon| icon.content.trim().to_string()).unwrap_or_default()
+        })
+    }).collect();
+    // Get [Desktop Action ...] groups in the order of Actions= key
+
+    Some(AppList {
+        id: id.to_string(),
+        name,
+        untranslated_name,
+        generic_name,
+        description,
+        keywords,
+        categories,
+        aliases: Vec::new(),
+        exec,
+        icon: icon_name,
+        icon_path: icon_path.unwrap_or_default(),
+        desktop_path: entry.to_path_buf(),
+        type_file: desktop_file.entry.entry_type.to_string(),
+        terminal,
+        try_exec,
+        working_dir,
+        actions
+    })
+}
+
+fn show_in_desktop(only_show_in: &Option<Vec<String>>, not_show_in: &Option<Vec<String>>, desktops: &[String]) -> bool {
+    for desktop in desktops {
+        if only_show_in.as_ref().is_some_and(|only| only.contains(desktop)) {
+            return true;
+        }
+        if not_show_in.as_ref().is_some_and(|not| not.contains(desktop)) {
+            return false;
         }
     }
-    apps_info
+    // First desktop from $XDG_CURRENT_DESKTOP that is in one of lists decides
+    only_show_in.is_none()
+    // If nothing matches, app is shown only when OnlyShowIn is not set
 }
@@ -1,17 +1,71 @@
-use std::{fs, path::PathBuf, vec};
+use std::{env, ffi::OsStr, fs, path::{Path, PathBuf}};
 
-use crate::core::apps::utils::flatpak_apps;
+use crate::core::apps::utils::expand_home;
 
-pub fn scan_desktop_files() -> Vec<PathBuf> {
-    let mut desktops_paths: Vec<PathBuf> = Vec::new();
+pub fn data_dirs() -> Vec<PathBuf> {
+    let mut data_dirs: Vec<PathBuf> = Vec::new();
 
-    for entry in vec!(fs::read_dir(dirs::home_dir().unwrap().join(".local/share/applications")), fs::read_dir(PathBuf::from("/usr/share/applications"))) // Get the apps in system apps dir and in user apps dir
-    {
-        if entry.is_ok() {
-            desktops_paths.extend(entry.unwrap().flatten().map(|e| e.path()));
-            // If entry don't get any errors push in desktops_paths all paths will get from system and user apps dir
+    if let Some(data_home) = dirs::data_dir() {
+        data_dirs.push(data_home);
+    }
+    // $XDG_DATA_HOME or ~/.local/share, it has the highest priority
+
+    let system_dirs = env::var("XDG_DATA_DIRS").ok().filter(|dirs| !dirs.is_empty()).unwrap_or("/usr/local/share:/usr/share".into());
+    data_dirs.extend(system_dirs.split(':').map(PathBuf::from).filter(|dir| dir.is_absolute()));
+    // $XDG_DATA_DIRS in order, relative paths are invalid by spec and ignored
+
+    if let Some(data_home) = dirs::data_dir() {
+        data_dirs.push(data_home.join("flatpak/exports/share"));
+    }
+    data_dirs.push(PathBuf::from("/var/lib/flatpak/exports/share"));
+    // Flatpak exports, in case session didn't add them to $XDG_DATA_DIRS
+
+    data_dirs
+}
+
+pub fn application_dirs(search_paths: &[String]) -> Vec<PathBuf> {
+    let mut app_dirs: Vec<PathBuf> = data_dirs().iter().map(|dir| dir.join("applications")).collect();
+    app_dirs.extend(search_paths.iter().map(|path| expand_home(path)));
+    // Extra dirs from config, they are applications dirs themselves
+
+    let mut unique: Vec<PathBuf> = Vec::new();
+    for dir in app_dirs {
+        if !unique.contains(&dir) {
+            unique.push(dir);
         }
     }
-    desktops_paths.extend(flatpak_apps().unwrap_or_default());
+    // Remove duplicates but keep the priority order
+    unique
+}
+
+pub fn scan_desktop_files(app_dirs: &[PathBuf]) -> Vec<(String, PathBuf)> {
+    let mut desktops_paths: Vec<(String, PathBuf)> = Vec::new();
+
+    for dir in app_dirs {
+        scan_dir(dir, "", &mut desktops_paths);
+        // Get the apps in every applications dir, in priority order
+    }
     desktops_paths
 }
+
+fn scan_dir(dir: &Path, prefix: &str, desktops_paths: &mut Vec<(String, PathBuf)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<(PathBuf, bool)> = entries.flatten().map(|e| (e.path(), e.file_type().is_ok_and(|file_type| file_type.is_dir()))).collect();
+    paths.sort();
+    // Sort so the order doesn't depend on the filesystem
+    // file_type doesn't follow symlinks, so a symlink loop can't make endless recursion
+
+    for (path, is_dir) in paths {
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if is_dir {
+            scan_dir(&path, &format!("{}{}-", prefix, file_name), desktops_paths);
+            // Desktop files can be in subdirectories, kde4/kate.desktop has id kde4-kate.desktop
+        } else if path.extension() == Some(OsStr::new("desktop")) {
+            desktops_paths.push((format!("{}{}", prefix, file_name), path));
+        }
+    }
+}
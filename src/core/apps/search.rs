@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use crate::core::apps::model::AppList;
+
+const SCORE_MATCH: i32 = 16;
+const BONUS_FIRST_CHAR: i32 = 10;
+const BONUS_WORD_START: i32 = 8;
+const BONUS_CAMEL_CASE: i32 = 7;
+const BONUS_CONSECUTIVE: i32 = 5;
+const PENALTY_GAP: i32 = 1;
+const MAX_GAP_PENALTY: i32 = 5;
+const BONUS_PREFIX: i32 = 50;
+const BONUS_EXACT: i32 = 100;
+const MAX_RECENT: usize = 5;
+// Empty search shows only the last few apps as recent
+const WEIGHT_GENERIC_NAME: i32 = 2;
+const WEIGHT_KEYWORD: i32 = 2;
+const WEIGHT_CATEGORY: i32 = 3;
+const WEIGHT_DESCRIPTION: i32 = 4;
+// Score of other fields is divided by weight, so they rank lower than the same match in name
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub index: usize,
+    pub positions: Vec<usize>,
+    // Chars of the app name that matched, for highlighting
+}
+
+pub fn empty_query(apps: &[AppList], order: &[String], pinned: &[String], recent: &[&str]) -> Vec<SearchResult> {
+    let position = |id: &str| apps.iter().position(|app| app.id == id);
+    let mut results: Vec<usize> = Vec::new();
+    for section in order {
+        let indexes: Vec<usize> = match section.as_str() {
+            "pinned" => pinned.iter().filter_map(|id| position(id)).collect(),
+            "recent" => recent.iter().take(MAX_RECENT).filter_map(|id| position(id)).collect(),
+            "all" => (0..apps.len()).collect(),
+            _ => Vec::new(),
+        };
+        for index in indexes {
+            if !results.contains(&index) {
+                results.push(index);
+            }
+        }
+        // Every app is shown once, in the first section it belongs to
+    }
+    results.into_iter().map(|index| SearchResult { index, positions: Vec::new() }).collect()
+}
+
+pub fn search(apps: &[AppList], query: &str, search_description: bool, boosts: &HashMap<&str, i32>) -> Vec<SearchResult> {
+    let query = query.trim();
+    let mut results: Vec<(usize, i32)> = apps.iter().enumerate().filter_map(|(index, app)| {
+        let score = app_score(app, query, search_description)?;
+        Some((index, score + boosts.get(app.id.as_str()).copied().unwrap_or(0)))
+        // Apps launched often, recently or with this query go up, but only if they match
+    }).collect();
+
+    results.sort_by(|(a_index, a_score), (b_index, b_score)| {
+        b_score.cmp(a_score).then_with(|| apps[*a_index].name.len().cmp(&apps[*b_index].name.len()))
+    });
+    // Best score first, shorter name wins a tie, otherwise keep the usual order
+    results.into_iter().map(|(index, _)| SearchResult {
+        index,
+        positions: fuzzy_match(query, &apps[index].name).map(|(_, positions)| positions).unwrap_or_default()
+        // Highlight the name even if another field matched better
+    }).collect()
+}
+
+fn app_score(app: &AppList, query: &str, search_description: bool) -> Option<i32> {
+    let name = [&app.name, &app.untranslated_name].into_iter().chain(&app.aliases).filter_map(|text| fuzzy_score(query, text)).max();
+    // Match translated and english name and aliases from config, the best one counts
+
+    let generic_name = fuzzy_score(query, &app.generic_name).map(|score| score / WEIGHT_GENERIC_NAME);
+    let keyword = app.keywords.iter().filter_map(|keyword| fuzzy_score(query, keyword)).max().map(|score| score / WEIGHT_KEYWORD);
+    let category = app.categories.iter().filter_map(|category| fuzzy_score(query, category)).max().map(|score| score / WEIGHT_CATEGORY);
+
+    let description = if search_description && app.description.to_lowercase().contains(&query.to_lowercase()) {
+        fuzzy_score(query, &app.description).map(|score| score / WEIGHT_DESCRIPTION)
+    } else {
+        None
+    };
+    // Description is long, fuzzy match would find almost any query in it, so only whole text counts
+
+    [name, generic_name, keyword, category, description].into_iter().flatten().max()
+}
+
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    fuzzy_match(query, text).map(|(score, _)| score)
+}
+
+pub fn fuzzy_match(query: &str, text: &str) -> Option<(i32, Vec<usize>)> {
+    let query: Vec<char> = query.chars().map(lowercase).collect();
+    let text: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = text.iter().map(|&c| lowercase(c)).collect();
+    if query.is_empty() || query.len() > text.len() {
+        return None;
+    }
+
+    let bonuses: Vec<i32> = (0..text.len()).map(|index| char_bonus(&text, index)).collect();
+    let mut rows: Vec<Vec<Option<(i32, usize)>>> = Vec::with_capacity(query.len());
+    // rows[i][j] is the best score when query[i] is matched at text[j], with the place of query[i - 1]
+
+    for (query_index, &query_char) in query.iter().enumerate() {
+        let mut current: Vec<Option<(i32, usize)>> = vec![None; text.len()];
+        for text_index in query_index..text.len() {
+            if lower[text_index] != query_char {
+                continue;
+            }
+            let here = SCORE_MATCH + bonuses[text_index];
+            current[text_index] = match rows.last() {
+                None => Some((here - gap_penalty(text_index), 0)),
+                // Letters skipped before the first match cost a bit too
+                Some(previous) => (query_index - 1..text_index).filter_map(|last| {
+                    let (score, _) = previous[last]?;
+                    let gap = text_index - last - 1;
+                    let step = if gap == 0 { BONUS_CONSECUTIVE } else { -gap_penalty(gap) };
+                    Some((score + here + step, last))
+                }).max_by_key(|(score, _)| *score),
+            };
+            // Try every position of the previous query char and keep the best
+        }
+        rows.push(current);
+    }
+
+    let (mut score, mut last) = rows.last()?.iter().enumerate()
+        .filter_map(|(text_index, cell)| cell.map(|(score, _)| (score, text_index)))
+        .max_by_key(|(score, _)| *score)?;
+    // Every query char must be found in order, otherwise it is not a match
+
+    let mut positions: Vec<usize> = vec![0; query.len()];
+    for query_index in (0..query.len()).rev() {
+        positions[query_index] = last;
+        last = rows[query_index][last].map(|(_, previous)| previous).unwrap_or(0);
+    }
+    // Walk back from the last char to get where every query char was matched
+
+    let query: String = query.iter().collect();
+    let lower: String = lower.iter().collect();
+    if lower == query {
+        score += BONUS_EXACT;
+    } else if lower.starts_with(&query) {
+        score += BONUS_PREFIX;
+    }
+    Some((score, positions))
+}
+
+fn char_bonus(text: &[char], index: usize) -> i32 {
+    if index == 0 {
+        return BONUS_FIRST_CHAR;
+    }
+    let (before, current) = (text[index - 1], text[index]);
+    if !before.is_alphanumeric() && current.is_alphanumeric() {
+        BONUS_WORD_START
+    } else if before.is_lowercase() && current.is_uppercase() {
+        BONUS_CAMEL_CASE
+        // KeePassXC, LibreOffice
+    } else {
+        0
+    }
+}
+
+fn gap_penalty(gap: usize) -> i32 {
+    (gap as i32 * PENALTY_GAP).min(MAX_GAP_PENALTY)
+}
+
+fn lowercase(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+    // One char for one char, so positions stay the same
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(id: &str, name: &str) -> AppList {
+        AppList { id: id.into(), name: name.into(), untranslated_name: name.into(), ..Default::default() }
+    }
+
+    fn names(apps: &[AppList], results: &[SearchResult]) -> Vec<String> {
+        results.iter().map(|result| apps[result.index].name.clone()).collect()
+    }
+
+    #[test]
+    fn abbreviation_finds_app() {
+        let (score, positions) = fuzzy_match("ffx", "Firefox").unwrap();
+        assert!(score > 0);
+        assert_eq!(positions, vec![0, 4, 6]);
+        assert_eq!(fuzzy_match("fxf", "Firefox"), None);
+        // Query chars must be in order
+    }
+
+    #[test]
+    fn word_start_ranks_above_middle_of_word() {
+        assert!(fuzzy_score("code", "Visual Studio Code") > fuzzy_score("code", "Barcode Scanner"));
+    }
+
+    #[test]
+    fn results_are_sorted_by_score() {
+        let apps = vec![app("barcode.desktop", "Barcode Scanner"), app("files.desktop", "Files"), app("code.desktop", "Visual Studio Code")];
+        let results = search(&apps, "code", false, &HashMap::new());
+        assert_eq!(names(&apps, &results), vec!["Visual Studio Code", "Barcode Scanner"]);
+        assert_eq!(results[0].positions, vec![14, 15, 16, 17]);
+        // Highlight belongs to the row it is shown on
+
+        let boosts = HashMap::from([("barcode.desktop", 1000)]);
+        let results = search(&apps, "code", false, &boosts);
+        assert_eq!(names(&apps, &results), vec!["Barcode Scanner", "Visual Studio Code"]);
+    }
+
+    #[test]
+    fn empty_query_sections() {
+        let apps = vec![app("a", "Alpha"), app("b", "Beta"), app("c", "Gamma")];
+        let order = vec!["pinned".to_string(), "recent".to_string(), "all".to_string()];
+        let results = empty_query(&apps, &order, &["c".to_string()], &["b", "c"]);
+        assert_eq!(names(&apps, &results), vec!["Gamma", "Beta", "Alpha"]);
+        // Pinned app is not shown again as recent
+    }
+
+    #[test]
+    fn ties_keep_list_order() {
+        let apps = vec![app("b", "Term B"), app("a", "Term A"), app("long", "Terminal")];
+        let results = search(&apps, "term", false, &HashMap::new());
+        assert_eq!(names(&apps, &results), vec!["Term B", "Term A", "Terminal"]);
+        // List and keyboard both use these results, so the same order must come out every time
+    }
+}
@@ -0,0 +1,57 @@
+use std::{fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use toml::{from_str, to_string};
+
+use crate::core::apps::storage::save_atomic;
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct State {
+    #[serde(default)]
+    pub pinned: Vec<String>,
+    #[serde(default)]
+    pub hidden: Vec<String>,
+}
+// Things changed from inside Stryde, config.toml is never written by Stryde
+
+impl State {
+    pub fn load() -> State {
+        state_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn toggle_pin(&mut self, id: &str) {
+        if let Some(position) = self.pinned.iter().position(|pinned| pinned == id) {
+            self.pinned.remove(position);
+        } else {
+            self.pinned.push(id.to_string());
+        }
+        // New pins go to the end, so older ones keep their place
+        if let Err(e) = self.save() {
+            println!("Failed to save state: {}", e);
+        }
+    }
+
+    pub fn hide(&mut self, id: &str) {
+        if !self.hidden.iter().any(|hidden| hidden == id) {
+            self.hidden.push(id.to_string());
+        }
+        self.pinned.retain(|pinned| pinned != id);
+        // Hidden app can't stay pinned
+        if let Err(e) = self.save() {
+            println!("Failed to save state: {}", e);
+        }
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = state_path().ok_or("no data dir")?;
+        Ok(save_atomic(&path, to_string(self)?.as_bytes())?)
+    }
+}
+
+fn state_path() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join("stryde/state.toml"))
+    // toml, so it can be edited by hand
+}
@@ -0,0 +1,55 @@
+use std::{fs, io, path::Path};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+pub fn load_versioned<T: DeserializeOwned>(path: &Path, magic: &[u8; 4], version: u32) -> Result<T, Box<dyn std::error::Error>> {
+    let data = fs::read(path)?;
+    let (header, body) = data.split_at_checked(8).ok_or("file is too short")?;
+    if &header[..4] != magic || header[4..] != version.to_le_bytes() {
+        return Err("file has an old format".into());
+    }
+    // Never deserialize file written by another Stryde version
+    Ok(bincode::deserialize(body)?)
+}
+
+pub fn save_versioned<T: Serialize>(path: &Path, magic: &[u8; 4], version: u32, value: &T) -> Result<(), Box<dyn std::error::Error>> {
+    let mut data = magic.to_vec();
+    data.extend_from_slice(&version.to_le_bytes());
+    data.extend(bincode::serialize(value)?);
+    Ok(save_atomic(path, &data)?)
+    // Magic and version first, then bincode body
+}
+
+pub fn save_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp_path = path.with_extension(format!("tmp.{}", std::process::id()));
+    // Own temp file for every Stryde, so two of them never write into the same one
+    if let Err(e) = fs::write(&tmp_path, data).and_then(|_| fs::rename(&tmp_path, path)) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+    Ok(())
+    // Rename replaces the file at once, a crash or other Stryde never sees half of it
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn versioned_round_trip() {
+        let dir = env::temp_dir().join(format!("stryde-storage-test-{}", std::process::id()));
+        let path = dir.join("nested/data.bin");
+        save_versioned(&path, b"TEST", 2, &vec![1u32, 2, 3]).unwrap();
+        assert_eq!(load_versioned::<Vec<u32>>(&path, b"TEST", 2).unwrap(), vec![1, 2, 3]);
+        assert!(load_versioned::<Vec<u32>>(&path, b"TEST", 3).is_err());
+        assert!(load_versioned::<Vec<u32>>(&path, b"OTHR", 2).is_err());
+        assert_eq!(fs::read_dir(dir.join("nested")).unwrap().count(), 1);
+        // Temp file is gone after rename
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,85 @@
+use std::{env, path::Path};
+
+use crate::{core::apps::{exec::split_exec, utils::find_in_path}, toml_files::BehaviorConfig};
+
+const TERMINALS: [(&str, &[&str]); 19] = [
+    ("xdg-terminal-exec", &[]),
+    ("kitty", &[]),
+    ("foot", &[]),
+    ("footclient", &[]),
+    ("alacritty", &["-e"]),
+    ("ghostty", &["-e"]),
+    ("wezterm", &["start", "--"]),
+    ("gnome-terminal", &["--"]),
+    ("ptyxis", &["--"]),
+    ("konsole", &["-e"]),
+    ("xfce4-terminal", &["-x"]),
+    ("mate-terminal", &["-x"]),
+    ("terminator", &["-x"]),
+    ("sakura", &["-x"]),
+    ("lxterminal", &["-e"]),
+    ("qterminal", &["-e"]),
+    ("urxvt", &["-e"]),
+    ("st", &["-e"]),
+    ("xterm", &["-e"]),
+];
+// Arguments put between terminal and the command, every terminal wants them a bit different
+// Also the order terminals are looked for in $PATH
+
+const UNKNOWN_TERMINAL_ARGS: &[&str] = &["-e"];
+// Most terminals understand -e
+
+pub fn terminal_command(argv: &[String], behavior: &BehaviorConfig) -> Option<Vec<String>> {
+    if !behavior.terminal_template.trim().is_empty() {
+        return from_template(&behavior.terminal_template, argv);
+    }
+    // Template from config wins over the table
+
+    let terminal = find_terminal(&behavior.default_terminal)?;
+    let mut command = vec![terminal.clone()];
+    command.extend(terminal_args(&terminal).iter().map(|arg| arg.to_string()));
+    command.extend(argv.iter().cloned());
+    Some(command)
+}
+
+fn from_template(template: &str, argv: &[String]) -> Option<Vec<String>> {
+    let mut command: Vec<String> = Vec::new();
+    let mut replaced = false;
+    for arg in split_exec(template)? {
+        if arg == "{}" {
+            command.extend(argv.iter().cloned());
+            replaced = true;
+        } else {
+            command.push(arg);
+        }
+    }
+    if !replaced {
+        command.extend(argv.iter().cloned());
+    }
+    // Command goes in place of {}, or at the end if there is none
+    (!command.is_empty()).then_some(command)
+}
+
+fn find_terminal(configured: &str) -> Option<String> {
+    let configured = configured.trim();
+    if !configured.is_empty() && find_in_path(configured).is_some() {
+        return Some(configured.to_string());
+    }
+    // Terminal from config, if it is installed
+
+    if let Ok(terminal) = env::var("TERMINAL")
+        && !terminal.is_empty()
+        && find_in_path(&terminal).is_some() {
+        return Some(terminal);
+    }
+    // User wide choice of terminal
+
+    TERMINALS.iter().map(|(name, _)| *name).find(|name| find_in_path(name).is_some()).map(String::from)
+    // xdg-terminal-exec first, it runs the terminal the user picked for the desktop
+}
+
+fn terminal_args(terminal: &str) -> &'static [&'static str] {
+    let name = Path::new(terminal).file_name().and_then(|name| name.to_str()).unwrap_or(terminal);
+    TERMINALS.iter().find(|(known, _)| *known == name).map(|(_, args)| *args).unwrap_or(UNKNOWN_TERMINAL_ARGS)
+    // Terminal can be set with full path, only its name is compared
+}
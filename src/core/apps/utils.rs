@@ -1,107 +1,173 @@
-use std::{fs, path::{Path, PathBuf}, time::UNIX_EPOCH};
+use std::{collections::HashMap, env, fs, os::unix::fs::PermissionsExt, path::{Path, PathBuf}, process::ExitStatus, time::UNIX_EPOCH};
 
-use freedesktop_icons::lookup;
-use iced::{Task, widget, window::{self}};
-use image::ImageReader;
+use iced::futures::channel::oneshot::Receiver;
+use image::{ImageReader, RgbaImage};
+use rayon::prelude::*;
 
-use crate::{ui::app::Message};
+use crate::{core::apps::{exec::{expand_exec, split_exec}, icon_cache::cached_icon, icons::IconResolver, launch::{scope_command, spawn_detached}, model::{AppAction, AppList, Handler}, terminal::terminal_command}, toml_files::Config};
 
-pub fn open_app(entry_exec: String, close_after_launch: bool, default_terminal: String, terminal: bool) -> Task<Message> {
-    let path = std::path::Path::new(&entry_exec);
-    if terminal {
-        if let Err(e) = std::process::Command::new(default_terminal).arg("-e").arg(&entry_exec).spawn() {
-            println!("Failed to open {:?}: {}", path, e);
+pub fn open_app(entry: &AppList, action: Option<&AppAction>, config: &Config) -> Result<(Receiver<ExitStatus>, Option<String>), String> {
+    let (exec, icon) = match action {
+        Some(action) if !action.icon.is_empty() => (&action.exec, &action.icon),
+        Some(action) => (&action.exec, &entry.icon),
+        None => (&entry.exec, &entry.icon),
+    };
+    // Desktop action runs its own Exec, icon falls back to the app icon
+
+    let argv = expand_exec(exec, icon, entry).ok_or(format!("Failed to open {}: invalid Exec line {:?}", entry.name, exec))?;
+    // Turn Exec line into argv, no shell is involved
+
+    let argv = if entry.terminal {
+        terminal_command(&argv, &config.behavior).ok_or(format!("Failed to open {}: no terminal found, set default_terminal in config", entry.name))?
+    } else {
+        argv
+    };
+    // Terminal apps run inside a terminal, with the arguments that terminal needs
+
+    let argv = match config.launch.prefix.trim() {
+        "" => argv,
+        prefix => {
+            let mut command = split_exec(prefix).ok_or(format!("Failed to open {}: invalid launch prefix {:?}", entry.name, prefix))?;
+            command.extend(argv);
+            command
         }
-    }else {
-        if path.exists() && path.is_file() {
-            // If file exists, try run it
-            if let Err(e) = std::process::Command::new(path).spawn() {
-                println!("Failed to open {:?}: {}",path, e);
-                // Print error is cannot open
-            }
-        } else {
-            if let Err(e) = std::process::Command::new("sh").arg("-c").arg(&entry_exec).spawn() {
-               println!("Failed to open {:?}: {}", path, e);
-                // Print error is cannot open 
-            }
+    };
+    // Prefix like "uwsm app --" or "gamemoderun" runs every app, terminal included
+
+    let argv = if config.launch.systemd_scope {
+        scope_command(&argv, find_in_path("systemd-run").as_deref(), &entry.id, &entry.name)
+    } else {
+        argv
+    };
+    // Own cgroup for every app, so Stryde and the app don't share memory limits
+
+    let mut warning = None;
+    let working_dir = entry.working_dir.as_ref().map(|dir| expand_home(dir)).filter(|dir| {
+        let exists = dir.is_dir();
+        if !exists {
+            warning = Some(format!("Working dir {} of {} doesn't exist, started it without", dir.display(), entry.name));
         }
-    }
-    if close_after_launch {
-        return window::latest().and_then(window::close);
-    }
-    Task::none()
+        exists
+    });
+    // Some apps, like game launchers, must run from their own dir
+
+    let exit = spawn_detached(&argv, working_dir.as_deref(), config.launch.env.get(&entry.id)).map_err(|e| {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => format!("Failed to open {}: {} is not installed", entry.name, argv[0]),
+            std::io::ErrorKind::PermissionDenied => format!("Failed to open {}: {} is not executable", entry.name, argv[0]),
+            _ => format!("Failed to open {}: {}", entry.name, e),
+        }
+    })?;
+    Ok((exit, warning))
+    // Error and warning are shown in the window, receiver gets exit status of the app
 }
 
-pub fn last_modified(path: &Path) -> Option<u64> {
+pub fn file_stamp(path: &Path) -> Option<(u64, u64)> {
     let metadata = fs::metadata(path).ok()?;
     let modified = metadata.modified().ok()?;
     let duration = modified.duration_since(UNIX_EPOCH).ok()?;
-    Some(duration.as_secs())
+    Some((duration.as_nanos() as u64, metadata.len()))
+    // Modified time in nanoseconds and size, to know if file changed
 }
 
-pub fn get_icon_path(icon_name: &str) -> Option<PathBuf> {
-    // Search direct in breeze for apps
-    for size in [32, 48, 64] {
-        let direct_path = PathBuf::from(format!(
-            "/usr/share/icons/breeze/apps/{}/{}.svg",
-            size, icon_name
-        ));
-        if direct_path.exists() {
-            return Some(direct_path);
-        }
+pub fn get_icon_path(icon_name: &str, icons: &IconResolver) -> Option<PathBuf> {
+    if let Some(path) = icons.find(icon_name) {
+        return Some(path);
     }
+    // Icon from user icon theme, its parents, hicolor or pixmaps
 
-    // Then specific themes
-    for theme in ["Breeze", "Papirus", "Adwaita"] {
-        for size in [64, 48, 32] {
-            if let Some(path) = lookup(icon_name)
-                .with_theme(theme)
-                .with_size(size)
-                .find() {
-                return Some(path);
-            }
-        }
-    }
-    
-    // Try symbolic
-    let symbolic = format!("{}-symbolic", icon_name);
-    if let Some(path) = lookup(&symbolic).with_size(32).find() {
+    if let Some(path) = icons.find(&format!("{}-symbolic", icon_name)) {
         return Some(path);
     }
+    // Try symbolic
+    icons.find("application-x-executable")
     // Generic fallback
-    lookup("application-x-executable").with_size(48).find()
 }
 
-pub fn resize_icon(path: &str, size: u32) -> Option<iced::widget::image::Handle> {
+pub fn load_icons(icon_paths: &[PathBuf], icon_size: u16) -> HashMap<PathBuf, Handler> {
+    icon_paths.par_iter().filter_map(|icon_path| {
+        let ext = icon_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        // Get icon extension like svg or png
+        let stamp = file_stamp(icon_path);
+
+        if ext == "svg" {
+            let svg_handler = iced::widget::svg::Handle::from_path(icon_path.clone());
+            Some((icon_path.clone(), Handler { image_handler: None, svg_handler: Some(svg_handler), stamp }))
+        } else {
+            let img = cached_icon(icon_path, icon_size.into())?;
+            Some((icon_path.clone(), Handler { image_handler: Some(img), svg_handler: None, stamp }))
+        }
+    }).collect()
+    // Decode and resize icons on all cores
+}
+
+pub fn resize_icon(path: &str, size: u32) -> Option<RgbaImage> {
     let img = ImageReader::open(path)
         .ok()?
         .decode()
         .ok()?
         .to_rgba8();
 
-    let resized = image::imageops::resize(&img, size, size, image::imageops::FilterType::Lanczos3);
+    Some(image::imageops::resize(&img, size, size, image::imageops::FilterType::Lanczos3))
+}
 
-    Some(widget::image::Handle::from_rgba(resized.width(), resized.height(), resized.into_raw()))
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+    // Replace ~/ with home dir
 }
 
-pub fn flatpak_apps() -> Option<Vec<PathBuf>> {
-    // get desktop files for flatpak apps
-    let base = PathBuf::from("/var/lib/flatpak/app");
-    let entries = fs::read_dir(&base).ok()?;
-
-    let mut result = Vec::new();
-
-    for folder in entries.flatten().map(|e| e.path()) {
-        if folder.is_dir() {
-            let p = folder.join("current/active/export/share/applications");
-            if let Ok(desktop_files) = fs::read_dir(&p) {
-                for file in desktop_files.flatten() {
-                    result.push(file.path());
-                    // add all desktop files to list
-                }
-            }
-        }
+pub fn find_in_path(program: &str) -> Option<PathBuf> {
+    if program.contains('/') {
+        let path = expand_home(program);
+        return is_executable(&path).then_some(path);
     }
+    // Absolute or relative path is checked directly
+
+    let path_var = env::var_os("PATH")?;
+    env::split_paths(&path_var)
+        .map(|dir| dir.join(program))
+        .find(|path| is_executable(path))
+}
 
-    Some(result)
-}
\ No newline at end of file
+fn is_executable(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+pub fn current_desktops() -> Vec<String> {
+    env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .split(':')
+        .filter(|desktop| !desktop.is_empty())
+        .map(String::from)
+        .collect()
+    // $XDG_CURRENT_DESKTOP is a list like "ubuntu:GNOME"
+}
+
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text): (Vec<char>, Vec<char>) = (pattern.chars().collect(), text.chars().collect());
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    // Last * in pattern and the text position it was tried at
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+            // Let the * take one more char and try again
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+    // Only * can match the empty rest
+}
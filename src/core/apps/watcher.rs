@@ -0,0 +1,274 @@
+use std::{collections::HashMap, ffi::{CString, OsStr}, fs, io, mem, os::unix::ffi::OsStrExt, path::{Path, PathBuf}, ptr, thread};
+
+use iced::futures::{SinkExt, Stream, StreamExt, channel::mpsc};
+
+use crate::{core::apps::{icons::{IconResolver, icon_scale, user_icon_theme}, indexer::indexing, model::AppList, scanner::{application_dirs, data_dirs}, utils::get_icon_path}, toml_files::Config, ui::app::Message};
+
+const WATCH_MASK: u32 = libc::IN_CREATE | libc::IN_DELETE | libc::IN_CLOSE_WRITE | libc::IN_MOVED_FROM | libc::IN_MOVED_TO | libc::IN_ATTRIB;
+const PARENT_MASK: u32 = libc::IN_CREATE | libc::IN_MOVED_TO;
+const ICON_DEPTH: usize = 3;
+// Icon dirs like hicolor/48x48/apps, deeper dirs are not used for app icons
+const DEBOUNCE_MS: i32 = 500;
+// Package managers write many files at once, wait until they are done
+
+#[allow(clippy::ptr_arg)]
+// Subscription::run_with gives data by reference
+pub fn watch_apps(search_paths: &Vec<String>) -> impl Stream<Item = Message> + use<> {
+    let search_paths = search_paths.clone();
+    iced::stream::channel(10, async move |mut output| {
+        let (sender, mut receiver) = mpsc::unbounded::<()>();
+        thread::spawn(move || watch_loop(&search_paths, sender));
+        // inotify read is blocking, so it runs in own thread
+
+        while receiver.next().await.is_some() {
+            if output.send(Message::AppsChanged).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+pub fn reload_apps(config: &Config, scale_factor: f32) -> Vec<AppList> {
+    let mut apps = indexing(config).unwrap_or_default();
+    let scale = icon_scale(scale_factor);
+    let icons = IconResolver::new(&user_icon_theme(&config.icon_theme), config.layout.icon_size, scale);
+    for app in &mut apps {
+        if !app.icon.is_empty() && (scale != 1 || !app.icon_path.is_file()) {
+            app.icon_path = get_icon_path(&app.icon, &icons).unwrap_or_default();
+        }
+    }
+    // Icon could be installed after the desktop file, look it up again if it is missing
+    // Cache has icons for scale 1, on HiDPI screen every icon is looked up again
+    apps
+}
+
+fn watch_loop(search_paths: &[String], sender: mpsc::UnboundedSender<()>) {
+    let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+    if fd < 0 {
+        println!("Failed to start file watcher: {}", io::Error::last_os_error());
+        return;
+    }
+
+    let app_dirs = application_dirs(search_paths);
+    let mut watches = Watches { fd, dirs: HashMap::new(), limit_reached: false };
+    for dir in &app_dirs {
+        watches.watch_tree(dir, usize::MAX);
+    }
+    // Every applications dir with all subdirectories
+    for dir in icon_dirs() {
+        watches.watch_tree(&dir, ICON_DEPTH);
+    }
+    watches.watch_missing(&app_dirs);
+    // Dirs are walked once, later only new subdirectories are added
+
+    let mut buffer = [0u8; 4096];
+    while let Some(mut changed) = watches.read_events(&mut buffer) {
+        while wait_events(fd, DEBOUNCE_MS) {
+            match watches.read_events(&mut buffer) {
+                Some(more) => changed |= more,
+                None => break,
+            }
+        }
+        // Collect all events until nothing happens for a while
+        changed |= watches.watch_missing(&app_dirs);
+
+        if changed && sender.unbounded_send(()).is_err() {
+            break;
+            // UI is closed
+        }
+    }
+    unsafe { libc::close(fd) };
+}
+
+fn icon_dirs() -> Vec<PathBuf> {
+    let mut icon_dirs: Vec<PathBuf> = data_dirs().iter().map(|dir| dir.join("icons")).collect();
+    if let Some(home) = dirs::home_dir() {
+        icon_dirs.push(home.join(".icons"));
+    }
+    icon_dirs.push(PathBuf::from("/usr/share/pixmaps"));
+    icon_dirs
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Watched {
+    Tree(usize),
+    // Dir and this many levels of its subdirectories
+    Parent,
+    // Nearest existing parent of applications dir that doesn't exist yet, only waits for it
+}
+
+struct Watches {
+    fd: i32,
+    dirs: HashMap<i32, (PathBuf, Watched)>,
+    limit_reached: bool,
+}
+
+impl Watches {
+    fn watch_tree(&mut self, dir: &Path, depth: usize) {
+        if !dir.is_dir() {
+            return;
+        }
+        self.add(dir, Watched::Tree(depth));
+        if depth == 0 {
+            return;
+        }
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                self.watch_tree(&entry.path(), depth - 1);
+            }
+        }
+        // file_type doesn't follow symlinks, so a symlink loop can't make endless recursion
+    }
+
+    fn watch_missing(&mut self, app_dirs: &[PathBuf]) -> bool {
+        let mut made = false;
+        let mut parents: Vec<PathBuf> = Vec::new();
+        for dir in app_dirs {
+            if self.dirs.values().any(|(path, watched)| path == dir && matches!(watched, Watched::Tree(_))) {
+                continue;
+            }
+            if dir.is_dir() {
+                self.watch_tree(dir, usize::MAX);
+                made = true;
+                // Made since last check, apps in it must be indexed
+            } else if let Some(parent) = dir.ancestors().skip(1).find(|parent| parent.is_dir()) {
+                parents.push(parent.to_path_buf());
+            }
+        }
+        // Fresh ~/.local/share/applications is made after Stryde starts, its parent tells when
+
+        let unused: Vec<i32> = self.dirs.iter()
+            .filter(|(_, (path, watched))| *watched == Watched::Parent && !parents.contains(path))
+            .map(|(wd, _)| *wd)
+            .collect();
+        for wd in unused {
+            unsafe { libc::inotify_rm_watch(self.fd, wd) };
+            self.dirs.remove(&wd);
+        }
+        // Parent is not needed once the dir below it exists
+        for parent in parents {
+            self.add(&parent, Watched::Parent);
+        }
+        made
+    }
+
+    fn add(&mut self, dir: &Path, watched: Watched) {
+        let Ok(path) = CString::new(dir.as_os_str().as_bytes()) else {
+            return;
+        };
+        let mask = match watched {
+            Watched::Tree(_) => WATCH_MASK,
+            Watched::Parent => PARENT_MASK | libc::IN_MASK_ADD,
+            // Mask is added, so a dir that is watched fully stays watched fully
+        };
+        let wd = unsafe { libc::inotify_add_watch(self.fd, path.as_ptr(), mask) };
+        if wd < 0 {
+            let error = io::Error::last_os_error();
+            if error.raw_os_error() != Some(libc::ENOSPC) || !self.limit_reached {
+                println!("Failed to watch {:?}: {}", dir, error);
+            }
+            if error.raw_os_error() == Some(libc::ENOSPC) && !self.limit_reached {
+                println!("Raise fs.inotify.max_user_watches, new apps in unwatched dirs show up only after restart");
+                self.limit_reached = true;
+            }
+            // Limit is printed once, not for every dir after it
+            return;
+        }
+        let watched = match (self.dirs.get(&wd), watched) {
+            (Some((_, Watched::Tree(old))), Watched::Tree(new)) => Watched::Tree((*old).max(new)),
+            (Some((_, old @ Watched::Tree(_))), Watched::Parent) => *old,
+            (_, watched) => watched,
+        };
+        self.dirs.insert(wd, (dir.to_path_buf(), watched));
+        // Watching same dir again just returns the old watch
+    }
+
+    fn read_events(&mut self, buffer: &mut [u8]) -> Option<bool> {
+        let read = unsafe { libc::read(self.fd, buffer.as_mut_ptr().cast(), buffer.len()) };
+        if read <= 0 {
+            return (read < 0 && io::Error::last_os_error().kind() == io::ErrorKind::Interrupted).then_some(false);
+        }
+
+        let header = mem::size_of::<libc::inotify_event>();
+        let mut changed = false;
+        let mut offset = 0;
+        while offset + header <= read as usize {
+            let event = unsafe { ptr::read_unaligned(buffer.as_ptr().add(offset).cast::<libc::inotify_event>()) };
+            let name_end = (offset + header + event.len as usize).min(read as usize);
+            let name = buffer[offset + header..name_end].split(|byte| *byte == 0).next().unwrap_or_default();
+            changed |= self.handle_event(event.wd, event.mask, OsStr::from_bytes(name));
+            offset = name_end;
+        }
+        Some(changed)
+    }
+
+    fn handle_event(&mut self, wd: i32, mask: u32, name: &OsStr) -> bool {
+        if mask & libc::IN_Q_OVERFLOW != 0 {
+            return true;
+            // Some events were lost, reload to be safe
+        }
+        if mask & libc::IN_IGNORED != 0 {
+            self.dirs.remove(&wd);
+            return false;
+            // Dir was deleted, its parent already reported it
+        }
+        match self.dirs.get(&wd).cloned() {
+            Some((dir, Watched::Tree(depth))) => {
+                if mask & libc::IN_ISDIR != 0 && mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0 && depth > 0 {
+                    self.watch_tree(&dir.join(name), depth - 1);
+                }
+                // Only new subdirectory gets watches, not the whole tree again
+                true
+            }
+            Some((_, Watched::Parent)) | None => false,
+            // Parents are checked after all events by watch_missing
+        }
+    }
+}
+
+fn wait_events(fd: i32, timeout_ms: i32) -> bool {
+    let mut poll_fd = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+    unsafe { libc::poll(&mut poll_fd, 1, timeout_ms) > 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn is_tree(watches: &Watches, dir: &Path) -> bool {
+        watches.dirs.values().any(|(path, watched)| path == dir && matches!(watched, Watched::Tree(_)))
+    }
+
+    #[test]
+    fn missing_and_new_dirs_are_watched() {
+        let root = env::temp_dir().join(format!("stryde-watcher-test-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let app_dir = root.join("share/applications");
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+        let mut watches = Watches { fd, dirs: HashMap::new(), limit_reached: false };
+        let mut buffer = [0u8; 4096];
+
+        assert!(!watches.watch_missing(std::slice::from_ref(&app_dir)));
+        assert!(watches.dirs.values().any(|(path, watched)| *path == root && *watched == Watched::Parent));
+        // Only the existing parent is watched
+
+        fs::create_dir_all(&app_dir).unwrap();
+        assert_eq!(watches.read_events(&mut buffer), Some(false));
+        assert!(watches.watch_missing(std::slice::from_ref(&app_dir)));
+        assert!(is_tree(&watches, &app_dir));
+        assert!(watches.dirs.values().all(|(_, watched)| *watched != Watched::Parent));
+        // Made dir is watched and means a reload, parent watch is dropped
+
+        fs::create_dir(app_dir.join("kde4")).unwrap();
+        assert_eq!(watches.read_events(&mut buffer), Some(true));
+        assert!(is_tree(&watches, &app_dir.join("kde4")));
+        fs::write(app_dir.join("kde4/kate.desktop"), "").unwrap();
+        assert_eq!(watches.read_events(&mut buffer), Some(true));
+        // New subdirectory is watched without walking the tree again
+
+        unsafe { libc::close(fd) };
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
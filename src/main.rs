@@ -1,44 +1,41 @@
 mod core;
-use std::{collections::HashMap, path::PathBuf};
 
-use crate::{core::apps::{indexer::indexing, model::Handler, utils::resize_icon}, toml_files::{BehaviorConfig, Config, Keybinds, KeybindsConfig, LayoutConfig, TextConfig, WindowConfig, read_theme, settings, string_to_named_key}, ui::app::run_ui};
+use std::collections::HashMap;
+
+use crate::{core::apps::indexer::indexing, toml_files::{AppsConfig, BehaviorConfig, Config, HistoryConfig, Keybinds, KeybindsConfig, LaunchConfig, LayoutConfig, TextConfig, WindowConfig, read_theme, settings, string_to_named_key}, ui::app::run_ui};
 mod ui;
 mod toml_files;
 fn main() -> iced::Result
 {
-    let apps = indexing().unwrap_or_default();
     let config: Config = settings().unwrap_or(
         Config {
             theme: "Stryde-Dark".into(),
 
+            icon_theme: "".into(),
+
             antialiasing: false,
 
             window: WindowConfig { width: 774, height: 500 },
 
-            text: TextConfig { font_name: " ".into(), list_text_size: 16, input_text_size: 18, placeholder: "Type commands, search...".into() },
+            text: TextConfig { font_name: " ".into(), list_text_size: 16, input_text_size: 18, placeholder: "Type commands, search...".into(), description_text_size: 13 },
 
-            layout: LayoutConfig { icon_size: 37, padding_vertical: 0.0, spacing: 5, divider: true },
+            layout: LayoutConfig { icon_size: 37, padding_vertical: 0.0, spacing: 5, divider: true, show_description: false, description_max_length: 60 },
 
-            behavior: BehaviorConfig { show_apps: true, close_on_launch: true, highlight_style_text: false, default_terminal: "kitty".into() },
+            behavior: BehaviorConfig { show_apps: true, close_on_launch: true, highlight_style_text: false, default_terminal: "".into(), terminal_template: "".into(), empty_query: vec!["pinned".into(), "recent".into(), "all".into()] },
             
-            keybinds: KeybindsConfig { close: "escape".into(), open: "enter".into(), navigation: vec!["arrowup".into(), "arrowdown".into()] }
+            keybinds: KeybindsConfig { close: "escape".into(), open: "enter".into(), navigation: vec!["arrowup".into(), "arrowdown".into()], actions: "tab".into(), pin: "f2".into(), hide: "f3".into() },
+
+            apps: AppsConfig { search_paths: vec![], search_description: false, pinned: vec![], hidden_apps: vec![] },
+
+            history: HistoryConfig { enabled: true, max_entries: 1000 },
+
+            entries: vec![],
+
+            launch: LaunchConfig { systemd_scope: false, prefix: "".into(), env: HashMap::new() }
         }
     );
+    let apps = indexing(&config).unwrap_or_default();
     let keybinds: Keybinds = string_to_named_key(&config.keybinds);
-    let mut icons: HashMap<PathBuf, Handler> = HashMap::new();
-    for entry in &apps {
-        let ext = entry.icon_path.extension().and_then(|e| e.to_str()).unwrap_or("");
-        // Get icon extension like svg or png
-
-        if ext == "svg" {
-            let svg_handler = iced::widget::svg::Handle::from_path(entry.icon_path.clone());
-            icons.insert(entry.icon_path.clone(), Handler { image_handler: None, svg_handler: Some(svg_handler) });
-        }else {
-            if let Some(img) = resize_icon(entry.icon_path.as_path().to_str().unwrap_or_default(), config.layout.icon_size.into()) {
-                icons.insert(entry.icon_path.clone(), Handler { image_handler: Some(img), svg_handler: None });
-            }
-        }
-    }
     // Get settings if get any errors put the default one
     let theme = read_theme(&config.theme).unwrap_or(
         iced::Theme::custom(
@@ -47,12 +44,12 @@ fn main() -> iced::Result
                 background: iced::Color::from_rgb(0.063, 0.063, 0.071),
                 text: iced::Color::WHITE,
                 primary: iced::Color::from_rgb(137.0/255.0, 180.0/255.0, 250.0/255.0),
-                success: iced::Color::from_rgb(0.306, 0.306, 0.318),
+                success: iced::Color::from_rgb(78.0/255.0, 78.0/255.0, 81.0/255.0),
                 danger: iced::Color::from_rgb(25.0/255.0, 25.0/255.0, 28.0/255.0),
                 warning: iced::Color::from_rgb(216.0/255.0, 68.0/255.0, 52.0/255.0)
             },
         )
     );
     // Get theme if get any errors put the default one
-    run_ui(apps, config, theme, icons, keybinds)
+    run_ui(apps, config, theme, keybinds)
 }
@@ -7,12 +7,22 @@ use toml::{from_str, to_string};
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct Config {
     pub theme: String,
+    #[serde(default)]
+    pub icon_theme: String,
     pub antialiasing: bool,
     pub window: WindowConfig,
     pub text: TextConfig,
     pub layout: LayoutConfig,
     pub behavior: BehaviorConfig,
     pub keybinds: KeybindsConfig,
+    #[serde(default)]
+    pub apps: AppsConfig,
+    #[serde(default)]
+    pub history: HistoryConfig,
+    #[serde(default)]
+    pub entries: Vec<EntryConfig>,
+    #[serde(default)]
+    pub launch: LaunchConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
@@ -27,6 +37,8 @@ pub struct TextConfig {
     pub list_text_size: u16,
     pub input_text_size: u16,
     pub placeholder: String,
+    #[serde(default = "default_description_text_size")]
+    pub description_text_size: u16,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
@@ -35,6 +47,10 @@ pub struct LayoutConfig {
     pub padding_vertical: f32,
     pub spacing: u16,
     pub divider: bool,
+    #[serde(default)]
+    pub show_description: bool,
+    #[serde(default = "default_description_max_length")]
+    pub description_max_length: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
@@ -43,20 +59,109 @@ pub struct BehaviorConfig {
     pub close_on_launch: bool,
     pub highlight_style_text: bool,
     pub default_terminal: String,
+    #[serde(default)]
+    pub terminal_template: String,
+    #[serde(default = "default_empty_query")]
+    pub empty_query: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct AppsConfig {
+    #[serde(default)]
+    pub search_paths: Vec<String>,
+    #[serde(default)]
+    pub search_description: bool,
+    #[serde(default)]
+    pub pinned: Vec<String>,
+    #[serde(default)]
+    pub hidden_apps: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct LaunchConfig {
+    #[serde(default)]
+    pub systemd_scope: bool,
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(default)]
+    pub env: HashMap<String, HashMap<String, String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct EntryConfig {
+    pub name: String,
+    #[serde(default)]
+    pub exec: String,
+    #[serde(default)]
+    pub alias: String,
+    #[serde(default)]
+    pub icon: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub terminal: bool,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct HistoryConfig {
+    pub enabled: bool,
+    pub max_entries: usize,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self { enabled: true, max_entries: 1000 }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct KeybindsConfig {
     pub close: String,
     pub open: String,
-    pub navigation: Vec<String>
+    pub navigation: Vec<String>,
+    #[serde(default = "default_actions_key")]
+    pub actions: String,
+    #[serde(default = "default_pin_key")]
+    pub pin: String,
+    #[serde(default = "default_hide_key")]
+    pub hide: String
 }
 
 #[derive(Debug, Clone)]
 pub struct Keybinds {
     pub close: Named,
     pub open: Named,
-    pub navigation: Vec<Named>
+    pub navigation: Vec<Named>,
+    pub actions: Named,
+    pub pin: Named,
+    pub hide: Named
+}
+
+fn default_description_text_size() -> u16 {
+    13
+}
+
+fn default_description_max_length() -> usize {
+    60
+}
+
+fn default_actions_key() -> String {
+    "tab".into()
+}
+
+fn default_pin_key() -> String {
+    "f2".into()
+}
+
+fn default_hide_key() -> String {
+    "f3".into()
+}
+
+fn default_empty_query() -> Vec<String> {
+    vec!["pinned".into(), "recent".into(), "all".into()]
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -84,17 +189,27 @@ pub fn settings() -> Option<Config>{
         let config = Config {
             theme: "Stryde-Dark".into(),
 
+            icon_theme: "".into(),
+
             antialiasing: false,
 
             window: WindowConfig { width: 774, height: 500 },
 
-            text: TextConfig { font_name: " ".into(), list_text_size: 16, input_text_size: 18, placeholder: "Type commands, search...".into() },
+            text: TextConfig { font_name: " ".into(), list_text_size: 16, input_text_size: 18, placeholder: "Type commands, search...".into(), description_text_size: 13 },
+
+            layout: LayoutConfig { icon_size: 37, padding_vertical: 0.0, spacing: 5, divider: true, show_description: false, description_max_length: 60 },
+
+            behavior: BehaviorConfig { show_apps: true, close_on_launch: true, highlight_style_text: false, default_terminal: "".into(), terminal_template: "".into(), empty_query: vec!["pinned".into(), "recent".into(), "all".into()] },
 
-            layout: LayoutConfig { icon_size: 37, padding_vertical: 0.0, spacing: 5, divider: true },
+            keybinds: KeybindsConfig { close: "escape".into(), open: "enter".into(), navigation: vec!["arrowup".into(), "arrowdown".into()], actions: "tab".into(), pin: "f2".into(), hide: "f3".into() },
 
-            behavior: BehaviorConfig { show_apps: true, close_on_launch: true, highlight_style_text: false, default_terminal: "kitty".into() },
+            apps: AppsConfig { search_paths: vec![], search_description: false, pinned: vec![], hidden_apps: vec![] },
 
-            keybinds: KeybindsConfig { close: "escape".into(), open: "enter".into(), navigation: vec!["arrowup".into(), "arrowdown".into()] }
+            history: HistoryConfig { enabled: true, max_entries: 1000 },
+
+            entries: vec![],
+
+            launch: LaunchConfig { systemd_scope: false, prefix: "".into(), env: HashMap::new() }
         };
         // Default settings
 
@@ -149,7 +264,7 @@ pub fn read_theme(using_theme: &str) -> Option<iced::Theme>{
 
                         primary: hex_to_rgb(&theme.primary).unwrap_or(iced::Color::from_rgb(137.0/255.0, 180.0/255.0, 250.0/255.0)),
 
-                        success: hex_to_rgb(&theme.secondary).unwrap_or(Color::from_rgb(0.306, 0.306, 0.318)),
+                        success: hex_to_rgb(&theme.secondary).unwrap_or(Color::from_rgb(78.0/255.0, 78.0/255.0, 81.0/255.0)),
 
                         danger: hex_to_rgb(&theme.selected).unwrap_or(Color::from_rgb(25.0/255.0, 25.0/255.0, 28.0/255.0)),
 
@@ -169,7 +284,7 @@ pub fn read_theme(using_theme: &str) -> Option<iced::Theme>{
                 background: Color::from_rgb(0.063, 0.063, 0.071),
                 text: Color::WHITE,
                 primary: iced::Color::from_rgb(137.0/255.0, 180.0/255.0, 250.0/255.0),
-                success: Color::from_rgb(0.306, 0.306, 0.318),
+                success: Color::from_rgb(78.0/255.0, 78.0/255.0, 81.0/255.0),
                 danger: Color::from_rgb(25.0/255.0, 25.0/255.0, 28.0/255.0),
                 warning: Color::from_rgb(216.0/255.0, 68.0/255.0, 52.0/255.0)
             },
@@ -191,7 +306,7 @@ fn hex_to_rgb(hex: &str) -> Option<Color> {
 
 
 
-    return Some(Color::from_rgb(r.ok()? as f32 / 255.0, g.ok()? as f32 / 255.0, b.ok()? as f32 / 255.0))
+    Some(Color::from_rgb(r.ok()? as f32 / 255.0, g.ok()? as f32 / 255.0, b.ok()? as f32 / 255.0))
 }
 
 pub fn string_to_named_key(string_keybinds: &KeybindsConfig) -> Keybinds {
@@ -240,6 +355,14 @@ pub fn string_to_named_key(string_keybinds: &KeybindsConfig) -> Keybinds {
     // Get open keybind
     let navigation= vec![map.get(&string_keybinds.navigation[0] as &str).copied().unwrap_or(Named::ArrowUp), map.get(&string_keybinds.navigation[1] as &str).copied().unwrap_or(Named::ArrowDown)];
     // Get keybind for navigation
+    let actions = map.get(&string_keybinds.actions as &str).copied().unwrap_or(Named::Tab);
+    // Get keybind for app actions submenu
+
+    let pin = map.get(&string_keybinds.pin as &str).copied().unwrap_or(Named::F2);
+    // Get keybind for pinning the selected app
+
+    let hide = map.get(&string_keybinds.hide as &str).copied().unwrap_or(Named::F3);
+    // Get keybind for hiding the selected app
 
-    Keybinds { close: close, open: open, navigation: navigation, }
+    Keybinds { close, open, navigation, actions, pin, hide }
 }
\ No newline at end of file
@@ -1,11 +1,11 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{collections::{HashMap, HashSet}, path::PathBuf};
 
 
-use iced::{Element, Font, Padding, Pixels, Settings, Size, Subscription, Task, Theme, event, keyboard::{self, Key}, theme::Palette, widget::{Column, Id, operation::{focus, scroll_to}, scrollable::{AbsoluteOffset}}, window::{self, settings::PlatformSpecific}};
+use iced::{Element, Font, Padding, Pixels, Settings, Size, Subscription, Task, Theme, event, keyboard::{self, Key, key::Named}, theme::Palette, widget::{Column, Id, operation::{focus, scroll_to}, scrollable::{AbsoluteOffset}}, window::{self, settings::PlatformSpecific}};
 
-use crate::{core::apps::{model::{AppList, Handler}, utils::open_app}, toml_files::{Config, Keybinds}, ui::widgets::{input_with_list::input_with_list, list_apps::list_apps}};
+use crate::{core::apps::{history::History, launch::early_exit_error, model::{AppList, Handler}, search::{SearchResult, empty_query, search}, state::State, utils::{file_stamp, load_icons, open_app}, watcher::{reload_apps, watch_apps}}, toml_files::{Config, Keybinds}, ui::widgets::{input_with_list::input_with_list, list_actions::list_actions, list_apps::list_apps}};
 
-pub fn run_ui(apps: Vec<AppList>, settings: Config, theme: Theme, handlers: HashMap<PathBuf, Handler>, keybinds: Keybinds) -> iced::Result{
+pub fn run_ui(apps: Vec<AppList>, settings: Config, theme: Theme, keybinds: Keybinds) -> iced::Result{
     let font_name = if !settings.text.font_name.is_empty() {
         Box::leak(settings.text.font_name.clone().into_boxed_str())
         // 0.03-0.05 KB memory leak :(
@@ -39,12 +39,12 @@ pub fn run_ui(apps: Vec<AppList>, settings: Config, theme: Theme, handlers: Hash
         ..Default::default()
     };
 
-    let antialiasing = settings.antialiasing.clone();
-    let list_text_size = settings.text.list_text_size.clone() as u32;
+    let antialiasing = settings.antialiasing;
+    let list_text_size = settings.text.list_text_size as u32;
 
     iced::application(
          move || {
-            let stryde = StrydeUI::new(apps.to_owned(), theme.to_owned(), settings.to_owned(), handlers.to_owned(), keybinds.to_owned());
+            let stryde = StrydeUI::new(apps.to_owned(), theme.to_owned(), settings.to_owned(), keybinds.to_owned());
 
         let focus_task = focus::<Message>("input");
         // Auto focus to input_text
@@ -52,14 +52,18 @@ pub fn run_ui(apps: Vec<AppList>, settings: Config, theme: Theme, handlers: Hash
         let task = Task::batch(vec![
             window::latest().and_then(window::gain_focus),
             // Auto focus to app
-            focus_task
+            focus_task,
+            stryde.load_missing_icons(),
+            // Window shows right away, icons fill in when they are loaded
+            window::latest().and_then(window::scale_factor).map(Message::ScaleChanged)
+            // Icons for HiDPI screen are loaded again once the scale is known
         ]);
         (stryde, task)
         },
         StrydeUI::update, StrydeUI::view).settings(Settings {
         id: Some("stryde".into()),
         default_text_size: Pixels::from(list_text_size),
-        antialiasing: antialiasing,
+        antialiasing,
         vsync: true,
         // simple text render
         fonts: vec![],
@@ -71,45 +75,83 @@ pub fn run_ui(apps: Vec<AppList>, settings: Config, theme: Theme, handlers: Hash
     .run()
 }
 
+const ICONS_PER_TASK: usize = 16;
+
 #[derive(Debug, Clone)]
 pub enum Message {
     SearchChanged(String),
-    Open(String, bool, String, bool),
-    KeyEvent(Key)
+    Open(usize, Option<usize>),
+    KeyEvent(Key),
+    AppsChanged,
+    AppsUpdated(Vec<AppList>),
+    IconsLoaded(u16, HashMap<PathBuf, Handler>),
+    // Pixel size icons were loaded for
+    ScaleChanged(f32),
+    LaunchChecked(Option<String>)
 }
 
 pub struct StrydeUI {
     text: String,
     app_list: Vec<AppList>,
+    results: Vec<SearchResult>,
+    // Indexes of shown apps, best match first
     selected: usize,
+    expanded: bool,
+    selected_action: usize,
     theme: Theme,
     config: Config,
     handlers: HashMap<PathBuf, Handler>,
+    history: History,
+    state: State,
+    scale_factor: f32,
+    error: Option<String>,
+    // Last launch error or notice, shown above the list
+    waiting_for_launch: bool,
+    // App was launched and window closes once it didn't fail right away
     keybinds_custom: Keybinds
 }
 
 impl StrydeUI {
-    fn new(app_list: Vec<AppList>, theme: Theme, config: Config, handlers: HashMap<PathBuf, Handler>, keybinds: Keybinds) -> Self {
+    fn new(app_list: Vec<AppList>, theme: Theme, config: Config, keybinds: Keybinds) -> Self {
         // make new app state with list of apps
-        Self {
+        let history = if config.history.enabled { History::load() } else { History::default() };
+        // Launch history is read once, then kept in memory
+        let mut stryde = Self {
             text: "".into(),
+            results: Vec::new(),
             app_list,
             selected: 0,
-            theme: theme,
-            config: config,
-            handlers: handlers,
+            expanded: false,
+            selected_action: 0,
+            theme,
+            config,
+            handlers: HashMap::new(),
+            history,
+            state: State::load(),
+            scale_factor: 1.0,
+            error: None,
+            waiting_for_launch: false,
             keybinds_custom: keybinds
-        }
+        };
+        stryde.update_results();
+        stryde
     }
 
     fn subscription(&self) -> Subscription<Message> {
         // listen for keyboard event
-        event::listen_with(|event, _status, _| match event {
+        let keyboard = event::listen_with(|event, _status, _| match event {
             iced::Event::Keyboard(iced::keyboard::Event::KeyPressed { key, .. }) => {
                 Some(Message::KeyEvent(key))
             }
+            iced::Event::Window(window::Event::Rescaled(scale_factor)) => {
+                Some(Message::ScaleChanged(scale_factor))
+                // Window moved to a monitor with another scale
+            }
             _ => None,
-        })
+        });
+        let watcher = Subscription::run_with(self.config.apps.search_paths.clone(), watch_apps);
+        // listen for installed and removed apps
+        Subscription::batch([keyboard, watcher])
     }
 
     fn theme(&self) -> Theme {
@@ -128,58 +170,259 @@ impl StrydeUI {
         )
     }
 
+    fn update_results(&mut self) {
+        if self.text.trim().is_empty() {
+            let pinned: Vec<String> = self.pinned().cloned().collect();
+            self.results = empty_query(&self.app_list, &self.config.behavior.empty_query, &pinned, &self.history.recent());
+            return;
+        }
+        // Empty search shows pinned, recent and other apps in the order from config
+        let boosts = self.history.boosts(&self.text);
+        self.results = search(&self.app_list, &self.text, self.config.apps.search_description, &boosts);
+        // View and keys use the same list, so selected row is always the shown one
+    }
+
+    fn pinned(&self) -> impl Iterator<Item = &String> {
+        self.config.apps.pinned.iter().chain(self.state.pinned.iter().filter(|id| !self.config.apps.pinned.contains(id)))
+        // Pins from config first, then the ones pinned with keybind
+    }
+
+    fn toggle_pin(&mut self) -> Task<Message> {
+        let Some(app_index) = self.selected_index() else {
+            return Task::none();
+        };
+        let id = self.app_list[app_index].id.clone();
+        if self.config.apps.pinned.contains(&id) {
+            self.error = Some(format!("{} is pinned in config.toml, remove it there to unpin", self.app_list[app_index].name));
+            return Task::none();
+            // Shown in the banner, config pins can't be changed from here
+        }
+        self.state.toggle_pin(&id);
+        self.error = None;
+        self.update_results();
+        self.selected = self.results.iter().position(|result| result.index == app_index).unwrap_or(0);
+        self.scroll_to_row(self.selected)
+        // Selection follows the app to its new place
+    }
+
+    fn hide_selected(&mut self) -> Task<Message> {
+        let Some(app_index) = self.selected_index() else {
+            return Task::none();
+        };
+        let app = self.app_list.remove(app_index);
+        self.state.hide(&app.id);
+        self.update_results();
+        self.selected = self.selected.min(self.results.len().saturating_sub(1));
+        Task::none()
+        // App is gone right away, next indexing filters it too
+    }
+
+    fn launch(&mut self, app_index: usize, action: Option<usize>) -> Task<Message> {
+        let Some(entry) = self.app_list.get(app_index) else {
+            return Task::none();
+        };
+        let action = action.and_then(|index| entry.actions.get(index));
+        let (exit, warning) = match open_app(entry, action, &self.config) {
+            Ok(launched) => launched,
+            Err(error) => {
+                self.error = Some(error);
+                return Task::none();
+                // Window stays open, so the error can be seen
+            }
+        };
+        self.error = warning;
+        // App started, but something about it is still worth telling
+
+        if self.config.history.enabled {
+            self.history.record(&entry.id, &self.text, self.config.history.max_entries);
+        }
+        // Remember the launch and what was typed for it
+
+        let name = entry.name.clone();
+        let check = Task::perform(early_exit_error(exit, name), Message::LaunchChecked);
+        if !self.config.behavior.close_on_launch || self.error.is_some() {
+            return check;
+        }
+        // Window with a warning stays open, it would be gone before it could be read
+        self.waiting_for_launch = true;
+        check
+        // Window stays until the app didn't fail right away, so the error can still be shown
+        // Not hidden meanwhile, Wayland doesn't allow hiding a window
+    }
+
+    fn selected_app(&self) -> Option<&AppList> {
+        self.selected_index().map(|index| &self.app_list[index])
+    }
+
+    fn selected_index(&self) -> Option<usize> {
+        self.results.get(self.selected).map(|result| result.index)
+    }
+
+    fn load_missing_icons(&self) -> Task<Message> {
+        let mut icon_paths: Vec<PathBuf> = Vec::new();
+        let mut seen: HashSet<&PathBuf> = HashSet::new();
+        for app in &self.app_list {
+            if !app.icon_path.as_os_str().is_empty() && !self.handlers.contains_key(&app.icon_path) && seen.insert(&app.icon_path) {
+                icon_paths.push(app.icon_path.clone());
+            }
+        }
+        // Icons in list order, so the first rows get icons first
+
+        let icon_size = self.icon_pixels();
+        Task::batch(icon_paths.chunks(ICONS_PER_TASK).map(|chunk| {
+            let chunk = chunk.to_vec();
+            Task::perform(async move { (icon_size, load_icons(&chunk, icon_size)) }, |(icon_size, icons)| Message::IconsLoaded(icon_size, icons))
+        }))
+        // Every chunk is shown as soon as it is ready
+    }
+
+    fn icon_pixels(&self) -> u16 {
+        (self.config.layout.icon_size as f32 * self.scale_factor).round() as u16
+        // Icons are drawn with real pixels, not logical ones
+    }
+
+    fn scroll_to_row(&self, row: usize) -> Task<Message> {
+        scroll_to(Id::new("scrollable"), AbsoluteOffset {
+            x: 0.0,
+            y: row as f32 * (50.0 + self.config.layout.spacing as f32)
+        })
+    }
+
     fn update(&mut self, message: Message) -> Task<Message>{
         match message {
+            Message::SearchChanged(_) | Message::Open(_, _) | Message::KeyEvent(_) if self.waiting_for_launch => Task::none(),
+            // Window waits for the app, input must not launch it again
             Message::SearchChanged(text) => {
                 self.text = text;
+                self.error = None;
+                self.update_results();
+                self.expanded = false;
                 if self.selected != 0 {
                     self.selected = 0;
                     return scroll_to(Id::new("scrollable"), AbsoluteOffset { x: 0.0, y: 0.0 });
-                } 
+                }
+                Task::none()
+            }
+            Message::Open(app_index, action) => self.launch(app_index, action),
+            Message::AppsChanged => {
+                let config = self.config.clone();
+                let scale_factor = self.scale_factor;
+                Task::perform(async move { reload_apps(&config, scale_factor) }, Message::AppsUpdated)
+                // Index apps again in background
+            }
+            Message::ScaleChanged(scale_factor) => {
+                if scale_factor == self.scale_factor {
                     return Task::none();
-                
+                }
+                self.scale_factor = scale_factor;
+                self.handlers.clear();
+                let config = self.config.clone();
+                Task::perform(async move { reload_apps(&config, scale_factor) }, Message::AppsUpdated)
+                // Look up icons for the new scale, they are loaded again after update
             }
-            Message::Open(entry_exec, close_after_launch, default_terminal, terminal) => {
-                    open_app(entry_exec, close_after_launch, default_terminal, terminal)
+            Message::AppsUpdated(apps) => {
+                self.app_list = apps;
+                let icon_paths: HashSet<&PathBuf> = self.app_list.iter().map(|app| &app.icon_path).collect();
+                self.handlers.retain(|path, handler| icon_paths.contains(path) && file_stamp(path) == handler.stamp);
+                // Forget icons no app uses anymore and icons changed on disk, changed ones are loaded again below
+                self.update_results();
+                self.expanded = false;
+                let len = self.results.len();
+                if self.selected >= len {
+                    self.selected = len.saturating_sub(1);
+                }
+                // Keep selection inside the new list
+                self.load_missing_icons()
+            }
+            Message::LaunchChecked(None) => {
+                if self.waiting_for_launch {
+                    return window::latest().and_then(window::close);
+                }
+                Task::none()
+            }
+            Message::LaunchChecked(Some(error)) => {
+                self.error = Some(error);
+                self.waiting_for_launch = false;
+                Task::none()
+                // Window stays open with the error
+            }
+            Message::IconsLoaded(icon_size, icons) => {
+                if icon_size == self.icon_pixels() {
+                    self.handlers.extend(icons);
+                }
+                // Icons started before the scale changed are too small or too big, new ones are on the way
+                Task::none()
             }
             Message::KeyEvent(key) => {
                 match key {
                     keyboard::Key::Named(named_key) => {
+                        if self.expanded {
+                            return self.actions_key_event(named_key);
+                        }
+                        // Submenu of the selected app takes the keys while it is open
+
                         if named_key == self.keybinds_custom.close {
                             return window::latest().and_then(window::close)
                         }
-                        if named_key == self.keybinds_custom.navigation[0]{
-                            if self.selected > 0 {
-                                self.selected -= 1;
-                                return scroll_to(Id::new("scrollable"), AbsoluteOffset {
-                                x: 0.0,
-                                y:  self.selected as f32 * (50.0 + self.config.layout.spacing as f32)
-                            });
-                            }
+                        if named_key == self.keybinds_custom.navigation[0] && self.selected > 0 {
+                            self.selected -= 1;
+                            return self.scroll_to_row(self.selected);
                         }
-                        if named_key == self.keybinds_custom.navigation[1] {
-                            if self.selected+1 < self.app_list.len() {
-                                self.selected += 1;
-                                return scroll_to(Id::new("scrollable"), AbsoluteOffset {
-                                x: 0.0,
-                                y: self.selected as f32 * (50.0 + self.config.layout.spacing as f32)
-                            });
-                            }
+                        if named_key == self.keybinds_custom.navigation[1] && self.selected+1 < self.results.len() {
+                            self.selected += 1;
+                            return self.scroll_to_row(self.selected);
                         }
-                        if named_key == self.keybinds_custom.open {
-                            let filtered: Vec<&AppList> = self.app_list.iter().filter(|app| {
-                                app.name.to_lowercase().contains(&self.text.to_lowercase())
-                            }).collect();
-
-                            return open_app(filtered[self.selected].exec.clone(), self.config.behavior.close_on_launch, self.config.behavior.default_terminal.clone(), filtered[self.selected].terminal.clone());
+                        if named_key == self.keybinds_custom.actions
+                            && self.selected_app().is_some_and(|entry| !entry.actions.is_empty()) {
+                            self.expanded = true;
+                            self.selected_action = 0;
+                            return self.scroll_to_row(self.selected + 1);
+                            // Open actions submenu only if the app has any
+                        }
+                        if named_key == self.keybinds_custom.pin {
+                            return self.toggle_pin();
                         }
-                        return Task::none()
+                        if named_key == self.keybinds_custom.hide {
+                            return self.hide_selected();
+                        }
+                        if named_key == self.keybinds_custom.open
+                            && let Some(app_index) = self.selected_index() {
+                            return self.launch(app_index, None);
+                        }
+                        Task::none()
                     },
                     _ => Task::none()
                 }
             }
         }
     }
+
+    fn actions_key_event(&mut self, named_key: Named) -> Task<Message> {
+        let Some(app_index) = self.selected_index() else {
+            self.expanded = false;
+            return Task::none();
+        };
+        let actions_len = self.app_list[app_index].actions.len();
+
+        if named_key == self.keybinds_custom.close || named_key == self.keybinds_custom.actions {
+            self.expanded = false;
+            return self.scroll_to_row(self.selected);
+            // Close submenu, not the window
+        }
+        if named_key == self.keybinds_custom.navigation[0] && self.selected_action > 0 {
+            self.selected_action -= 1;
+            return self.scroll_to_row(self.selected + 1 + self.selected_action);
+        }
+        if named_key == self.keybinds_custom.navigation[1] && self.selected_action+1 < actions_len {
+            self.selected_action += 1;
+            return self.scroll_to_row(self.selected + 1 + self.selected_action);
+        }
+        if named_key == self.keybinds_custom.open {
+            return self.launch(app_index, Some(self.selected_action));
+        }
+        Task::none()
+    }
+
     fn view(&self) -> iced::Element<'_, Message> {
         let mut list_column = Column::new().spacing(self.config.layout.spacing as u32).padding(
             Padding {
@@ -190,25 +433,38 @@ impl StrydeUI {
             }
         );
 
-        let filtered = self.app_list.iter().filter(|app| {
-                app.name.to_lowercase().contains(&self.text.to_lowercase())
-        });
-
-        for (index, entry) in filtered.enumerate() {
+        for (index, result) in self.results.iter().enumerate() {
+            let app_index = result.index;
+            let entry = &self.app_list[app_index];
+            let expanded = self.expanded && self.selected == index;
             list_column = list_column.push(
                 Element::from(
                     list_apps(
                         entry.name.clone(),
-                         entry.exec.clone(),
+                        result.positions.clone(),
+                        if entry.description.is_empty() { &entry.generic_name } else { &entry.description },
                           self.theme().clone(),
-                          self.selected == index,
-                          self.config.behavior.highlight_style_text,
-                          self.handlers.get(&entry.icon_path).unwrap_or(&Handler { image_handler: None, svg_handler: None }).clone(),
-                          self.config.layout.icon_size,
-                        ).on_press(Message::Open(entry.exec.clone(), self.config.behavior.close_on_launch, self.config.behavior.default_terminal.clone(), entry.terminal.clone()))))
+                          self.selected == index && !expanded,
+                          self.handlers.get(&entry.icon_path).unwrap_or(&Handler { image_handler: None, svg_handler: None, stamp: None }).clone(),
+                          &self.config,
+                        ).on_press(Message::Open(app_index, None))));
+
+            if expanded {
+                for (action_index, action) in entry.actions.iter().enumerate() {
+                    list_column = list_column.push(
+                        list_actions(
+                            action.name.clone(),
+                            self.theme(),
+                            self.selected_action == action_index,
+                            self.config.behavior.highlight_style_text,
+                            self.config.layout.icon_size,
+                        ).on_press(Message::Open(app_index, Some(action_index))));
+                }
+                // Show actions of the selected app under it
+            }
         } // Make a list with all apps
         
-        input_with_list(list_column, &self.text, &self.theme(), &self.config)
+        input_with_list(list_column, &self.text, &self.theme(), &self.config, self.error.as_deref())
         // Make a input, divider, list
     }
-}
\ No newline at end of file
+}
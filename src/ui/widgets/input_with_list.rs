@@ -12,9 +12,10 @@ pub fn input_with_list<'a>(
     text: &str,
     theme: &Theme,
     config: &Config,
+    error: Option<&str>,
 ) -> iced::Element<'a, Message> {
 
-    let list_column = if config.behavior.show_apps == false && text.is_empty() {
+    let list_column = if !config.behavior.show_apps && text.is_empty() {
        Column::new()
     }else {
         list_column
@@ -27,6 +28,22 @@ pub fn input_with_list<'a>(
         &config.text.placeholder
     };
 
+    let banner = error.map(|error| {
+        container(iced::widget::text(error.to_string()).color(palette.warning))
+            .padding(Padding {
+                top: 10.0,
+                right: 30.0,
+                bottom: 10.0,
+                left: 30.0,
+            })
+            .width(iced::Length::Fill)
+            .style(move |_theme: &Theme| container::Style {
+                background: Some(iced::Background::Color(Color { a: 0.15, ..palette.warning })),
+                ..container::Style::default()
+            })
+    });
+    // Launch error under the input, in warning color
+
     let divider_size: u16 = if config.layout.divider {
         1
     }else {
@@ -61,6 +78,7 @@ pub fn input_with_list<'a>(
                     bottom: 20.0,
                     left: 30.0,
                 }),
+            banner,
             // thin line under search
             rule::horizontal(divider_size as u32).style(move |_theme: &Theme| Style {
                 color: _theme.palette().success,
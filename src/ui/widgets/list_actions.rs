@@ -0,0 +1,51 @@
+use iced::{Alignment, Background, Border, Color, Length, Pixels, Shadow, Theme, widget::{Button, button, row, text}};
+
+use crate::ui::app::Message;
+
+pub fn list_actions(
+    name: String,
+    theme: Theme,
+    selected: bool,
+    highlight_text: bool,
+    icon_size: u16,
+) -> iced::widget::Button<'static, Message> {
+    let content = row![text(name)].align_y(Alignment::Center);
+
+    let palette = theme.palette();
+    let bg_color = if selected && !highlight_text {
+        palette.danger
+    } else {
+        palette.background
+    };
+    let text_color = if selected && highlight_text {
+        palette.primary
+    } else if selected {
+        palette.text
+    } else {
+        palette.success
+    };
+    // Not selected actions use secondary color so they look nested under the app
+    Button::new(content)
+        .padding(iced::Padding {
+            top: 5.0,
+            left: 25.0 + icon_size as f32 + 10.0,
+            right: 0.0,
+            bottom: 0.0,
+        })
+        // Align action text with app name, after the icon
+        .width(Length::Fill)
+        .height(50)
+        .style(
+            move |_theme: &Theme, _status: button::Status| button::Style {
+                background: Some(Background::Color(bg_color)),
+                text_color,
+                border: Border {
+                    color: Color::TRANSPARENT,
+                    width: 0.0,
+                    radius: iced::border::Radius::new(Pixels(0.0)),
+                },
+                shadow: Shadow::default(),
+                snap: false
+            },
+        )
+}
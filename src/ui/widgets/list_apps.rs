@@ -1,33 +1,51 @@
-use iced::{Alignment, Background, Border, Color, Length, Pixels, Shadow, Theme, widget::{Button, Row, button, image, row, svg, text}};
+use iced::{Alignment, Background, Border, Color, Length, Pixels, Shadow, Theme, widget::{Button, Column, Row, button, image, rich_text, row, span, svg, text, text::{Rich, Span}}};
 
-use crate::{core::apps::{model::Handler}, ui::app::Message};
+use crate::{core::apps::{model::Handler}, toml_files::Config, ui::app::Message};
 
 pub fn list_apps(
     name: String,
-    _exec: String,
+    matched: Vec<usize>,
+    description: &str,
     theme: Theme,
     selected: bool,
-    highlight_text: bool,
     handlers: Handler,
-    icon_size: u16,
+    config: &Config,
 ) -> iced::widget::Button<'static, Message> {
+    let highlight_text = config.behavior.highlight_style_text;
+    let icon_size = config.layout.icon_size;
+    let palette = theme.palette();
+    let match_color = if selected && highlight_text {
+        palette.text
+    } else {
+        palette.primary
+    };
+    // Selected text is already primary, so matched chars swap to text color
+    let name = highlighted_name(&name, &matched, match_color);
+
+    let mut label: Column<'static, Message> = Column::new().push(name);
+    if config.layout.show_description
+        && let Some(description) = short_description(description, config.layout.description_max_length) {
+        label = label.push(text(description).size(config.text.description_text_size as u32).color(palette.success));
+    }
+    // Second line with description in secondary color
+
     let mut _content: Row<'_, Message> = Row::new();
 
-    if !handlers.image_handler.is_none() || !handlers.svg_handler.is_none() {
+    if handlers.image_handler.is_some() || handlers.svg_handler.is_some() {
         // If icon exists, i show it
-        if !handlers.svg_handler.is_none() {
+        if handlers.svg_handler.is_some() {
             if let Some(svg_handle) = handlers.svg_handler.as_ref() {
                 _content = row![
                     svg(svg_handle.clone())
                         .width(icon_size as u32)
                         .height(icon_size as u32),
-                    text(name)
+                    label
                 ]
                 .spacing(10)
                 .align_y(iced::Alignment::Center);
             }
             else {
-                _content = row![text(name)];
+                _content = row![label];
             }
             // If icon is svg, i show with svg widget
         } else {
@@ -36,17 +54,16 @@ pub fn list_apps(
                 _content = row![image(img_handle)
                     .width(icon_size as u32)
                     .height(icon_size as u32),
-                text(name)
+                label
             ].spacing(10);
             }else {
-                _content = row![text(name)];
+                _content = row![label];
             }
         };
 
     } else {
-        _content = row![text(name)];
+        _content = row![label];
     }
-    let palette = theme.palette();
     let bg_color = if selected && !highlight_text {
         palette.danger
     }else {
@@ -71,7 +88,7 @@ pub fn list_apps(
                     // button bg from theme
                     background: Some(Background::Color(bg_color)),
                     // text from theme
-                    text_color: text_color,
+                    text_color,
                     // border no color and small round
                     border: Border {
                         color: Color::TRANSPARENT,
@@ -83,4 +100,48 @@ pub fn list_apps(
                     snap: false
                 },
             )
-}
\ No newline at end of file
+}
+
+fn highlighted_name(name: &str, matched: &[usize], match_color: Color) -> Rich<'static, (), Message> {
+    let mut spans: Vec<Span<'static, ()>> = Vec::new();
+    let mut part = String::new();
+    let mut part_matched = false;
+    for (index, c) in name.chars().enumerate() {
+        let is_matched = matched.contains(&index);
+        if is_matched != part_matched && !part.is_empty() {
+            spans.push(name_span(std::mem::take(&mut part), part_matched, match_color));
+        }
+        part_matched = is_matched;
+        part.push(c);
+    }
+    // Chars next to each other with the same state go to one span
+    if !part.is_empty() {
+        spans.push(name_span(part, part_matched, match_color));
+    }
+    rich_text(spans)
+}
+
+fn name_span(part: String, matched: bool, match_color: Color) -> Span<'static, ()> {
+    if matched {
+        span(part).color(match_color)
+    } else {
+        span(part)
+        // Not matched text uses the button text color
+    }
+}
+
+fn short_description(description: &str, max_length: usize) -> Option<String> {
+    let description = description.trim();
+    if description.is_empty() {
+        return None;
+    }
+    if max_length == 0 || description.chars().count() <= max_length {
+        return Some(description.to_string());
+    }
+    // 0 means no limit
+    let mut short: String = description.chars().take(max_length.saturating_sub(1)).collect();
+    short.truncate(short.trim_end().len());
+    short.push('…');
+    Some(short)
+    // Cut long text so the row stays one line, ellipsis counts as a char
+}
@@ -1,2 +1,3 @@
 pub mod input_with_list;
-pub mod list_apps;
\ No newline at end of file
+pub mod list_apps;
+pub mod list_actions;
\ No newline at end of file